@@ -0,0 +1,121 @@
+use game_lib::{
+    bevy::{ecs as bevy_ecs, prelude::*},
+    derive_more::{From, Into},
+};
+use game_tiles::{EntityWorldPosition, TileWorldPosition};
+
+/// How an entity jumps.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct JumpConfig {
+    /// Upwards velocity in `m/s` given by a jump.
+    pub jump_speed: f32,
+
+    /// Number of extra jumps that can be made before touching the ground.
+    pub air_jumps: u32,
+
+    /// Seconds after walking off of a ledge that a jump can still be made
+    /// from the ground.
+    pub coyote_time: f32,
+
+    /// Seconds before landing that a jump can be pressed and still be made
+    /// once the entity lands.
+    pub jump_buffer: f32,
+
+    /// Upwards velocity is multiplied by this when the jump button is released
+    /// early, allowing for shorter jumps.
+    pub release_multiplier: f32,
+
+    /// Velocity in `m/s` given by jumping off of a wall, pointing away from the
+    /// wall, or `None` to disable wall jumps.
+    pub wall_jump_velocity: Option<EntityWorldPosition>,
+}
+
+impl Default for JumpConfig {
+    fn default() -> Self {
+        JumpConfig {
+            jump_speed: 5.0,
+            air_jumps: 1,
+            coyote_time: 0.1,
+            jump_buffer: 0.1,
+            release_multiplier: 0.5,
+            wall_jump_velocity: Some(EntityWorldPosition::new(4.0, 5.0)),
+        }
+    }
+}
+
+/// Timers used to track the state of an entity's jumps.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct JumpState {
+    /// Seconds since the entity was last on the ground.
+    pub since_grounded: f32,
+
+    /// Seconds left before a buffered jump is forgotten.
+    pub buffered: f32,
+
+    /// Direction of the wall the entity last touched, `-1` for walls on its
+    /// left and `1` for walls on its right.
+    pub wall_direction: f32,
+
+    /// Seconds since the entity last touched a wall.
+    pub since_wall: f32,
+
+    /// Whether the entity is rising from a jump and hasn't released the jump
+    /// button yet.
+    pub rising: bool,
+}
+
+impl Default for JumpState {
+    fn default() -> Self {
+        JumpState {
+            since_grounded: 0.0,
+            buffered: 0.0,
+            wall_direction: 0.0,
+            since_wall: f32::INFINITY,
+            rising: false,
+        }
+    }
+}
+
+/// How an entity mines and places tiles.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct InteractionConfig {
+    /// Distance in `m` from the center of the entity to the center of the
+    /// furthest tile it can reach.
+    pub reach: f32,
+
+    /// Multiplier for how quickly tiles are mined.
+    pub mining_speed: f32,
+}
+
+impl Default for InteractionConfig {
+    fn default() -> Self {
+        InteractionConfig {
+            reach: 5.0,
+            mining_speed: 1.0,
+        }
+    }
+}
+
+/// The tile an entity is mining and how far along it is.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct MiningState {
+    /// Position of the tile being mined, if any.
+    pub target: Option<TileWorldPosition>,
+
+    /// Progress towards mining the target tile, in the range `[0, 1]`.
+    pub progress: f32,
+}
+
+/// Marks the sprite which highlights the tile under the cursor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct TileHighlight;
+
+/// Where the center of an entity is moved to after it dies.
+#[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct RespawnPoint(pub EntityWorldPosition);
@@ -1,9 +1,25 @@
+use crate::{InteractionConfig, JumpConfig, JumpState, MiningState, RespawnPoint};
+use game_health::{FallDamage, HealthBundle};
+use game_inventory::{Hotbar, Inventory};
 use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
-use game_physics::PhysicsBundle;
+use game_physics::{MoveDirection, MovementConfig, PhysicsBundle};
 
 #[derive(Default, Bundle)]
 pub struct PlayerBundle {
     pub player: Player,
+    pub jump_config: JumpConfig,
+    pub jump_state: JumpState,
+    pub movement_config: MovementConfig,
+    pub move_direction: MoveDirection,
+    pub interaction_config: InteractionConfig,
+    pub mining_state: MiningState,
+    pub inventory: Inventory,
+    pub hotbar: Hotbar,
+    pub fall_damage: FallDamage,
+    pub respawn_point: RespawnPoint,
+
+    #[bundle]
+    pub health_bundle: HealthBundle,
 
     #[bundle]
     pub sprite_bundle: SpriteBundle,
@@ -1,8 +1,10 @@
 pub(crate) mod systems;
 
+mod components;
 mod entity;
 mod plugin;
 
+pub use components::*;
 pub use entity::*;
 pub use plugin::*;
 
@@ -1,8 +1,11 @@
-use crate::Player;
+use crate::{
+    InteractionConfig, JumpConfig, JumpState, MiningState, Player, RespawnPoint, TileHighlight,
+};
 use game_camera::CameraSystem;
 use game_core::{GameStage, GlobalMode, ModeEvent, modes::ModeExt};
+use game_health::HealthSystem;
 use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
-use game_physics::PhysicsPlugin;
+use game_physics::{PhysicsPlugin, PhysicsSystem};
 use game_tiles::TileSystem;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
@@ -11,6 +14,26 @@ pub struct ControllerPlugin;
 impl Plugin for ControllerPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.register_type::<Player>()
+            .register_type::<JumpConfig>()
+            .register_type::<JumpState>()
+            .register_type::<InteractionConfig>()
+            .register_type::<MiningState>()
+            .register_type::<TileHighlight>()
+            .register_type::<RespawnPoint>()
+            .add_system_set_to_stage(
+                GameStage::GamePreUpdate,
+                SystemSet::new()
+                    .label(ControllerPlugin)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Enter))
+                    .with_system(crate::systems::setup_tile_highlight.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GamePostUpdate,
+                SystemSet::new()
+                    .label(ControllerPlugin)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Exit))
+                    .with_system(crate::systems::cleanup_tile_highlight.system()),
+            )
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
                 SystemSet::new()
@@ -24,8 +47,37 @@ impl Plugin for ControllerPlugin {
                     .label(ControllerPlugin)
                     .label(ControllerSystem::HandleControls)
                     .after(ControllerSystem::UpdateConfig)
+                    .before(PhysicsSystem::Prepare)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::move_player.system())
+                    .with_system(crate::systems::jump.system())
+                    .with_system(crate::systems::select_hotbar_slot.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(ControllerPlugin)
+                    .label(ControllerSystem::Interact)
+                    .after(ControllerSystem::HandleControls)
+                    .before(TileSystem::DetectChanges)
+                    .before(TileSystem::DetectRedraw)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::update_tile_highlight.system())
+                    .with_system(
+                        crate::systems::mine_tiles
+                            .system()
+                            .chain(crate::systems::place_tiles.system())
+                            .chain(crate::systems::throw_items.system()),
+                    ),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(ControllerPlugin)
+                    .label(ControllerSystem::Respawn)
+                    .after(HealthSystem::ApplyDamage)
                     .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
-                    .with_system(crate::systems::move_player.system()),
+                    .with_system(crate::systems::respawn_players.system()),
             )
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
@@ -44,5 +96,7 @@ impl Plugin for ControllerPlugin {
 pub enum ControllerSystem {
     UpdateConfig,
     HandleControls,
+    Interact,
+    Respawn,
     UpdateCamera,
 }
@@ -1,11 +1,19 @@
-use crate::Player;
+use crate::{
+    InteractionConfig, JumpConfig, JumpState, MiningState, Player, RespawnPoint, TileHighlight,
+};
 use game_camera::{CameraConfig, CameraMode, ScaledOrthographicProjection};
-use game_input::ActionInput;
+use game_health::{Died, Health, MaxHealth};
+use game_input::{ActionInput, CursorState};
+use game_inventory::{DropItem, Hotbar, Inventory, ItemRegistry, ItemStack};
 use game_lib::{
     bevy::{prelude::*, render::camera::Camera},
     tracing::{self, instrument},
 };
-use game_physics::{JumpStatus, Velocity};
+use game_physics::{
+    BodyType, DropThroughPlatforms, JumpStatus, MoveDirection, PreviousBounds, TileCollision,
+    TileCollisionAxis, Velocity,
+};
+use game_tiles::{EntityWorldPosition, EntityWorldRect, GameWorld, TileWorldPosition};
 
 #[instrument(skip(config, input))]
 pub fn cycle_camera_mode(mut config: ResMut<CameraConfig>, input: Res<Input<ActionInput>>) {
@@ -85,38 +93,401 @@ pub fn update_camera(
 #[instrument(skip(input, query))]
 pub fn move_player(
     input: Res<Input<ActionInput>>,
-    mut query: Query<(&mut Velocity, &mut JumpStatus), With<Player>>,
+    mut query: Query<(&mut MoveDirection, &mut DropThroughPlatforms), With<Player>>,
 ) {
     // Get direction to move
-    const MOVE_SPEED: f32 = 5.0;
-    const JUMP_SPEED: f32 = 5.0;
-    let mut move_velocity = Vec2::default();
-    let mut jump_velocity = None;
+    let mut move_direction = 0.0;
     if input.pressed(ActionInput::PlayerLeft) {
-        move_velocity -= Vec2::X * MOVE_SPEED;
+        move_direction -= 1.0;
     }
     if input.pressed(ActionInput::PlayerRight) {
-        move_velocity += Vec2::X * MOVE_SPEED;
+        move_direction += 1.0;
     }
-    if input.pressed(ActionInput::PlayerJump) {
-        jump_velocity = Some(Vec2::Y * JUMP_SPEED);
+    let drop_through = input.pressed(ActionInput::PlayerDown);
+
+    // Update movement, which is applied as forces by the physics engine
+    for (mut direction, mut drop_through_platforms) in query.iter_mut() {
+        if direction.0 != move_direction {
+            direction.0 = move_direction;
+        }
+
+        if drop_through_platforms.0 != drop_through {
+            drop_through_platforms.0 = drop_through;
+        }
     }
+}
 
-    // Apply force
-    for (mut velocity, mut jump_status) in query.iter_mut() {
-        if move_velocity.length_squared() >= 0.1 {
-            if move_velocity.x > 0.0 && move_velocity.x > velocity.0.x {
-                velocity.0.x = (velocity.0.x + move_velocity.x).min(move_velocity.x);
-            } else if move_velocity.x < 0.0 && move_velocity.x < velocity.0.x {
-                velocity.0.x = (velocity.0.x + move_velocity.x).max(move_velocity.x);
+#[instrument(skip(input, time, collisions, query))]
+pub fn jump(
+    input: Res<Input<ActionInput>>,
+    time: Res<Time>,
+    mut collisions: EventReader<TileCollision>,
+    mut query: Query<(&mut Velocity, &mut JumpStatus, &JumpConfig, &mut JumpState), With<Player>>,
+) {
+    const SWIM_SPEED: f32 = 3.0;
+    let delta = time.delta_seconds();
+    let pressed = input.pressed(ActionInput::PlayerJump);
+    let just_pressed = input.just_pressed(ActionInput::PlayerJump);
+
+    // Track which walls have been touched
+    for collision in collisions.iter() {
+        if collision.axis == TileCollisionAxis::X && collision.entity_velocity.x != 0.0 {
+            if let Ok((_, _, _, mut state)) = query.get_mut(collision.entity) {
+                state.wall_direction = collision.entity_velocity.x.signum();
+                state.since_wall = 0.0;
             }
         }
+    }
 
+    for (mut velocity, mut jump_status, config, mut state) in query.iter_mut() {
+        // Update timers
         if *jump_status == JumpStatus::OnGround {
-            if let Some(jump_velocity) = jump_velocity {
-                velocity.0.y = (velocity.0.y + jump_velocity.y).min(jump_velocity.y);
-                *jump_status = JumpStatus::InAir { jumps: 1 };
+            state.since_grounded = 0.0;
+        } else {
+            state.since_grounded += delta;
+        }
+        state.since_wall += delta;
+        state.buffered = if just_pressed {
+            config.jump_buffer
+        } else {
+            state.buffered - delta
+        };
+
+        // Cut the jump short if the button is released while rising
+        if state.rising && (!pressed || velocity.0.y <= 0.0) {
+            if velocity.0.y > 0.0 {
+                velocity.0.y *= config.release_multiplier;
+            }
+
+            state.rising = false;
+        }
+
+        // Swim upwards while the button is held
+        if *jump_status == JumpStatus::Swimming {
+            if pressed {
+                velocity.0.y = velocity.0.y.max(SWIM_SPEED);
+            }
+
+            continue;
+        }
+
+        if state.buffered <= 0.0 {
+            continue;
+        }
+
+        // Jumps used so far, where the ground jump is lost once coyote time
+        // runs out
+        let in_coyote_time = state.since_grounded <= config.coyote_time;
+        let jumps = match *jump_status {
+            JumpStatus::OnGround => 0,
+            JumpStatus::InAir { jumps: 0 } if in_coyote_time => 0,
+            JumpStatus::InAir { jumps } => jumps.max(1),
+            JumpStatus::Swimming => continue,
+        };
+
+        let wall_jump = config
+            .wall_jump_velocity
+            .filter(|_| jumps > 0 && state.since_wall <= config.coyote_time);
+        if let Some(wall_jump) = wall_jump {
+            // Jump away from the wall
+            velocity.0.x = -state.wall_direction * wall_jump.x;
+            velocity.0.y = wall_jump.y;
+            state.since_wall = f32::INFINITY;
+        } else if jumps <= config.air_jumps {
+            velocity.0.y = config.jump_speed;
+            *jump_status = JumpStatus::InAir { jumps: jumps + 1 };
+        } else {
+            continue;
+        }
+
+        state.buffered = 0.0;
+        state.since_grounded = f32::INFINITY;
+        state.rising = true;
+    }
+}
+
+/// Gets the position of the tile under the cursor.
+fn tile_under_cursor(cursor_state: &CursorState) -> TileWorldPosition {
+    let position = cursor_state.world_position.floor();
+    TileWorldPosition::new(position.x as i32, position.y as i32)
+}
+
+/// Checks whether the tile at the given position is within reach of an entity.
+fn in_reach(bounds: EntityWorldRect, config: &InteractionConfig, tile: TileWorldPosition) -> bool {
+    let tile_center = EntityWorldPosition::new(tile.x as f32 + 0.5, tile.y as f32 + 0.5);
+    let offset = tile_center - bounds.center();
+    offset.x * offset.x + offset.y * offset.y <= config.reach * config.reach
+}
+
+#[instrument(skip(commands, materials))]
+pub fn setup_tile_highlight(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                size: Vec2::ONE,
+                ..Default::default()
+            },
+            material: materials.add(ColorMaterial::color(Color::rgba(1.0, 1.0, 1.0, 0.3))),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(TileHighlight);
+}
+
+#[instrument(skip(commands, query))]
+pub fn cleanup_tile_highlight(mut commands: Commands, query: Query<Entity, With<TileHighlight>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[instrument(skip(cursor_state, player_query, highlight_query))]
+pub fn update_tile_highlight(
+    cursor_state: Res<CursorState>,
+    player_query: Query<(&EntityWorldRect, &InteractionConfig), With<Player>>,
+    mut highlight_query: Query<(&mut Transform, &mut Visible), With<TileHighlight>>,
+) {
+    let tile = tile_under_cursor(&cursor_state);
+    let visible = player_query
+        .iter()
+        .any(|(&bounds, config)| in_reach(bounds, config, tile));
+
+    for (mut transform, mut highlight_visible) in highlight_query.iter_mut() {
+        if highlight_visible.is_visible != visible {
+            highlight_visible.is_visible = visible;
+        }
+
+        let translation = Vec3::new(tile.x as f32 + 0.5, tile.y as f32 + 0.5, 1.0);
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+}
+
+#[instrument(skip(input, query))]
+pub fn select_hotbar_slot(
+    input: Res<Input<ActionInput>>,
+    mut query: Query<&mut Hotbar, With<Player>>,
+) {
+    let mut offset = 0;
+    if input.just_pressed(ActionInput::HotbarNext) {
+        offset += 1;
+    }
+    if input.just_pressed(ActionInput::HotbarPrevious) {
+        offset -= 1;
+    }
+    let slot = input.get_just_pressed().find_map(|&action| match action {
+        ActionInput::HotbarSlot(slot) => Some(slot as usize),
+        _ => None,
+    });
+
+    for mut hotbar in query.iter_mut() {
+        if let Some(slot) = slot.filter(|&slot| slot < hotbar.size) {
+            if hotbar.selected != slot {
+                hotbar.selected = slot;
+            }
+        }
+
+        if offset != 0 {
+            hotbar.cycle(offset);
+        }
+    }
+}
+
+#[instrument(skip(input, time, cursor_state, registry, world, drop_item, query))]
+pub fn mine_tiles(
+    input: Res<Input<ActionInput>>,
+    time: Res<Time>,
+    cursor_state: Res<CursorState>,
+    registry: Res<ItemRegistry>,
+    mut world: ResMut<GameWorld>,
+    mut drop_item: EventWriter<DropItem>,
+    mut query: Query<(&EntityWorldRect, &InteractionConfig, &mut MiningState), With<Player>>,
+) {
+    let tile = tile_under_cursor(&cursor_state);
+    let mining = input.pressed(ActionInput::PlayerMine);
+    for (&bounds, config, mut state) in query.iter_mut() {
+        // Get how long it takes to mine the tile, if it can be mined
+        let hardness = match world.get_tile(tile) {
+            Ok(Some(current)) if mining && in_reach(bounds, config, tile) => current.hardness(),
+            _ => None,
+        };
+        let hardness = match hardness {
+            Some(hardness) => hardness,
+            None => {
+                if state.target.is_some() {
+                    *state = MiningState::default();
+                }
+
+                continue;
+            }
+        };
+
+        // Start over when switching tiles
+        if state.target != Some(tile) {
+            state.target = Some(tile);
+            state.progress = 0.0;
+        }
+
+        state.progress += time.delta_seconds() * config.mining_speed / hardness.max(f32::EPSILON);
+        if state.progress >= 1.0 {
+            *state = MiningState::default();
+            let mined = match world.set_tile(tile, None) {
+                Ok(mined) => mined,
+                Err(error) => {
+                    warn!("failed to mine tile at {}: {}", tile, error);
+                    continue;
+                }
+            };
+
+            // Drop the mined tile where it was
+            let item = mined.and_then(|mined| registry.get_by_tile(mined));
+            if let Some(item) = item {
+                drop_item.send(DropItem {
+                    stack: ItemStack::new(item.id.clone(), 1),
+                    position: EntityWorldPosition::new(tile.x as f32 + 0.5, tile.y as f32 + 0.5),
+                    velocity: EntityWorldPosition::Y * 2.0,
+                });
             }
         }
     }
 }
+
+#[instrument(skip(input, cursor_state, registry, world, player_query, body_query))]
+pub fn place_tiles(
+    input: Res<Input<ActionInput>>,
+    cursor_state: Res<CursorState>,
+    registry: Res<ItemRegistry>,
+    mut world: ResMut<GameWorld>,
+    mut player_query: Query<
+        (
+            &EntityWorldRect,
+            &InteractionConfig,
+            &Hotbar,
+            &mut Inventory,
+        ),
+        With<Player>,
+    >,
+    body_query: Query<&EntityWorldRect, With<BodyType>>,
+) {
+    if !input.pressed(ActionInput::PlayerPlace) {
+        return;
+    }
+
+    // Tiles can't be placed inside of bodies, including dropped items
+    let tile = tile_under_cursor(&cursor_state);
+    let tile_bounds = EntityWorldRect::new(
+        EntityWorldPosition::new(tile.x as f32, tile.y as f32),
+        EntityWorldPosition::ONE,
+    );
+    if body_query
+        .iter()
+        .any(|&bounds| bounds.intersects(tile_bounds))
+    {
+        return;
+    }
+
+    for (&bounds, config, hotbar, mut inventory) in player_query.iter_mut() {
+        if !in_reach(bounds, config, tile) {
+            continue;
+        }
+
+        // Get the tile placed by the selected item
+        let selected = inventory
+            .get(hotbar.selected)
+            .and_then(|stack| registry.get(&stack.item))
+            .and_then(|item| item.tile);
+        let selected = match selected {
+            Some(selected) => selected,
+            None => continue,
+        };
+
+        // Only place tiles into empty space
+        if let Ok(None) = world.get_tile(tile) {
+            match world.set_tile(tile, Some(selected)) {
+                Ok(_) => {
+                    inventory.take(hotbar.selected, 1);
+                }
+                Err(error) => warn!("failed to place tile at {}: {}", tile, error),
+            }
+
+            break;
+        }
+    }
+}
+
+#[instrument(skip(input, cursor_state, drop_item, query))]
+pub fn throw_items(
+    input: Res<Input<ActionInput>>,
+    cursor_state: Res<CursorState>,
+    mut drop_item: EventWriter<DropItem>,
+    mut query: Query<(&EntityWorldRect, &Hotbar, &mut Inventory), With<Player>>,
+) {
+    const THROW_SPEED: f32 = 6.0;
+    if !input.just_pressed(ActionInput::PlayerThrow) {
+        return;
+    }
+
+    for (&bounds, hotbar, mut inventory) in query.iter_mut() {
+        // Throw one of the selected item towards the cursor
+        let stack = match inventory.take(hotbar.selected, 1) {
+            Some(stack) => stack,
+            None => continue,
+        };
+        let position = bounds.center();
+        let offset = cursor_state.world_position - Vec2::from(position);
+        let direction = if offset.length_squared() > 0.0 {
+            offset.normalize()
+        } else {
+            Vec2::ZERO
+        };
+        drop_item.send(DropItem {
+            stack,
+            position,
+            velocity: (direction * THROW_SPEED).into(),
+        });
+    }
+}
+
+#[instrument(skip(died, world, query))]
+pub fn respawn_players(
+    mut died: EventReader<Died>,
+    mut world: ResMut<GameWorld>,
+    mut query: Query<
+        (
+            &RespawnPoint,
+            &mut EntityWorldRect,
+            &mut PreviousBounds,
+            &mut Velocity,
+            &mut Health,
+            &MaxHealth,
+        ),
+        With<Player>,
+    >,
+) {
+    for event in died.iter() {
+        let (respawn_point, mut bounds, mut previous_bounds, mut velocity, mut health, max_health) =
+            match query.get_mut(event.entity) {
+                Ok(player) => player,
+                Err(_) => continue,
+            };
+
+        // Move the player without interpolating from where they died, using the
+        // world's spawn point if something was built at their respawn point.
+        // If there's no room anywhere, they respawn where they died.
+        let respawn_bounds = EntityWorldRect::from_center(respawn_point.0, bounds.size() / 2.0);
+        if world.is_clear(respawn_bounds) {
+            *bounds = respawn_bounds;
+        } else if let Some(spawn_bounds) = world.get_spawn_bounds(bounds.size()) {
+            *bounds = spawn_bounds;
+        } else {
+            warn!("no room to respawn the player");
+        }
+        previous_bounds.0 = None;
+        velocity.0 = EntityWorldPosition::ZERO;
+        health.0 = max_health.0;
+    }
+}
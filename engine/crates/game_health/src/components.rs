@@ -0,0 +1,98 @@
+use game_lib::{
+    bevy::{ecs as bevy_ecs, prelude::*},
+    derive_more::{Display, From, Into},
+};
+
+/// All the components needed for an entity to be able to take damage.
+#[derive(Bundle, Default)]
+pub struct HealthBundle {
+    pub health: Health,
+    pub max_health: MaxHealth,
+    pub invulnerable: Invulnerable,
+}
+
+/// Current health of an entity. The entity dies when this reaches zero.
+#[derive(Clone, Copy, PartialEq, Debug, Display, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct Health(pub f32);
+
+impl Default for Health {
+    fn default() -> Self {
+        Health(MaxHealth::default().0)
+    }
+}
+
+/// Health an entity has when it is fully healed.
+#[derive(Clone, Copy, PartialEq, Debug, Display, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct MaxHealth(pub f32);
+
+impl Default for MaxHealth {
+    fn default() -> Self {
+        MaxHealth(100.0)
+    }
+}
+
+/// Seconds left before the entity can take damage again. Entities become
+/// briefly invulnerable after taking damage so that damage sent many times in
+/// a row, like from touching something harmful, isn't applied every frame.
+#[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct Invulnerable(pub f32);
+
+impl Invulnerable {
+    /// Seconds an entity is invulnerable for after taking damage.
+    pub const AFTER_DAMAGE: f32 = 0.5;
+}
+
+/// Makes an entity take damage when it hits the ground too fast.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct FallDamage {
+    /// Fastest speed in `m/s` the entity can hit the ground at without taking
+    /// damage.
+    pub safe_speed: f32,
+
+    /// Damage taken for each `m/s` the entity hits the ground faster than the
+    /// safe speed.
+    pub damage_per_speed: f32,
+}
+
+impl Default for FallDamage {
+    fn default() -> Self {
+        FallDamage {
+            safe_speed: 12.0,
+            damage_per_speed: 5.0,
+        }
+    }
+}
+
+impl FallDamage {
+    /// Calculates the damage taken from hitting the ground at the given speed.
+    pub fn damage(&self, impact_speed: f32) -> f32 {
+        (impact_speed - self.safe_speed).max(0.0) * self.damage_per_speed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_fall_damage_up_to_safe_speed() {
+        let fall_damage = FallDamage::default();
+        assert_eq!(0.0, fall_damage.damage(0.0));
+        assert_eq!(0.0, fall_damage.damage(fall_damage.safe_speed - 1.0));
+        assert_eq!(0.0, fall_damage.damage(fall_damage.safe_speed));
+    }
+
+    #[test]
+    fn fall_damage_scales_above_safe_speed() {
+        let fall_damage = FallDamage {
+            safe_speed: 10.0,
+            damage_per_speed: 4.0,
+        };
+        assert_eq!(4.0, fall_damage.damage(11.0));
+        assert_eq!(20.0, fall_damage.damage(15.0));
+    }
+}
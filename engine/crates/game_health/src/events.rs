@@ -0,0 +1,37 @@
+use game_lib::bevy::prelude::*;
+
+/// Sent to damage an entity.
+#[derive(Clone, Debug, Reflect)]
+pub struct Damage {
+    pub entity: Entity,
+    pub amount: f32,
+    pub source: DamageSource,
+}
+
+/// What caused an entity to take damage.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum DamageSource {
+    /// The entity hit the ground too fast.
+    Fall,
+
+    /// Another entity damaged the entity.
+    Entity(Entity),
+
+    /// Anything else, such as a mod or a debug command.
+    Other,
+}
+
+impl Default for DamageSource {
+    fn default() -> Self {
+        DamageSource::Other
+    }
+}
+
+/// Sent when an entity's health reaches zero.
+#[derive(Clone, Debug, Reflect)]
+pub struct Died {
+    pub entity: Entity,
+
+    /// Source of the damage which killed the entity.
+    pub source: DamageSource,
+}
@@ -0,0 +1,11 @@
+pub(crate) mod systems;
+
+mod components;
+mod events;
+mod plugin;
+
+pub use components::*;
+pub use events::*;
+pub use plugin::*;
+
+game_lib::fix_bevy_derive!(game_lib::bevy);
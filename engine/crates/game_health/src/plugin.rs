@@ -0,0 +1,49 @@
+use crate::{Damage, DamageSource, Died, FallDamage, Health, Invulnerable, MaxHealth};
+use game_core::{modes::ModeExt, GameStage, GlobalMode, ModeEvent};
+use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
+use game_physics::PhysicsPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_type::<Health>()
+            .register_type::<MaxHealth>()
+            .register_type::<Invulnerable>()
+            .register_type::<FallDamage>()
+            .register_type::<DamageSource>()
+            .register_type::<Damage>()
+            .register_type::<Died>()
+            .add_event::<Damage>()
+            .add_event::<Died>()
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(HealthPlugin)
+                    .label(HealthSystem::DetectDamage)
+                    .after(PhysicsPlugin)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::apply_fall_damage.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(HealthPlugin)
+                    .label(HealthSystem::ApplyDamage)
+                    .after(HealthSystem::DetectDamage)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(
+                        crate::systems::update_invulnerability
+                            .system()
+                            .chain(crate::systems::apply_damage.system()),
+                    ),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub enum HealthSystem {
+    DetectDamage,
+    ApplyDamage,
+}
@@ -0,0 +1,218 @@
+use crate::{Damage, DamageSource, Died, FallDamage, Health, Invulnerable};
+use game_lib::{
+    bevy::{prelude::*, utils::HashMap},
+    tracing::{self, instrument},
+};
+use game_physics::{TileCollision, TileCollisionAxis};
+
+#[instrument(skip(collisions, damage, query))]
+pub fn apply_fall_damage(
+    mut collisions: EventReader<TileCollision>,
+    mut damage: EventWriter<Damage>,
+    query: Query<&FallDamage>,
+) {
+    // A body can land on several tiles at once, so only use the fastest impact
+    let mut impacts: HashMap<Entity, f32> = HashMap::default();
+    for collision in collisions.iter() {
+        if collision.axis == TileCollisionAxis::Y && collision.entity_velocity.y < 0.0 {
+            let speed = impacts.entry(collision.entity).or_default();
+            *speed = speed.max(-collision.entity_velocity.y);
+        }
+    }
+
+    for (entity, speed) in impacts {
+        if let Ok(fall_damage) = query.get(entity) {
+            let amount = fall_damage.damage(speed);
+            if amount > 0.0 {
+                damage.send(Damage {
+                    entity,
+                    amount,
+                    source: DamageSource::Fall,
+                });
+            }
+        }
+    }
+}
+
+#[instrument(skip(events, died, query))]
+pub fn apply_damage(
+    mut events: EventReader<Damage>,
+    mut died: EventWriter<Died>,
+    mut query: Query<(&mut Health, Option<&mut Invulnerable>)>,
+) {
+    for event in events.iter() {
+        let (mut health, invulnerable) = match query.get_mut(event.entity) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+
+        // Dead and invulnerable entities can't be damaged
+        if health.0 <= 0.0 || event.amount <= 0.0 {
+            continue;
+        }
+        if let Some(mut invulnerable) = invulnerable {
+            if invulnerable.0 > 0.0 {
+                continue;
+            }
+
+            invulnerable.0 = Invulnerable::AFTER_DAMAGE;
+        }
+
+        health.0 = (health.0 - event.amount).max(0.0);
+        if health.0 <= 0.0 {
+            died.send(Died {
+                entity: event.entity,
+                source: event.source,
+            });
+        }
+    }
+}
+
+#[instrument(skip(time, query))]
+pub fn update_invulnerability(time: Res<Time>, mut query: Query<&mut Invulnerable>) {
+    for mut invulnerable in query.iter_mut() {
+        if invulnerable.0 > 0.0 {
+            invulnerable.0 = (invulnerable.0 - time.delta_seconds()).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MaxHealth;
+    use game_tiles::{EntityWorldPosition, Tile, TileWorldPosition};
+
+    fn world() -> World {
+        let mut world = World::default();
+        world.insert_resource(Events::<Damage>::default());
+        world.insert_resource(Events::<Died>::default());
+        world
+    }
+
+    fn damage_stage() -> SystemStage {
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(apply_damage.system());
+        stage
+    }
+
+    fn send_damage(world: &mut World, entity: Entity, amount: f32) {
+        world
+            .get_resource_mut::<Events<Damage>>()
+            .unwrap()
+            .send(Damage {
+                entity,
+                amount,
+                source: DamageSource::Other,
+            });
+    }
+
+    /// Every entity that has died so far.
+    fn died(world: &World) -> Vec<Entity> {
+        let events = world.get_resource::<Events<Died>>().unwrap();
+        events
+            .get_reader()
+            .iter(events)
+            .map(|died| died.entity)
+            .collect()
+    }
+
+    fn health(world: &World, entity: Entity) -> f32 {
+        world.get::<Health>(entity).unwrap().0
+    }
+
+    #[test]
+    fn invulnerable_entities_ignore_damage() {
+        let mut world = world();
+        let mut stage = damage_stage();
+        let entity = world
+            .spawn()
+            .insert_bundle((Health(100.0), MaxHealth(100.0), Invulnerable(0.0)))
+            .id();
+
+        // Only the first of several hits in a row applies
+        send_damage(&mut world, entity, 10.0);
+        send_damage(&mut world, entity, 10.0);
+        stage.run(&mut world);
+        assert_eq!(90.0, health(&world, entity));
+        assert_eq!(
+            Invulnerable::AFTER_DAMAGE,
+            world.get::<Invulnerable>(entity).unwrap().0
+        );
+
+        send_damage(&mut world, entity, 10.0);
+        stage.run(&mut world);
+        assert_eq!(90.0, health(&world, entity));
+    }
+
+    #[test]
+    fn health_stops_at_zero_and_entity_dies_once() {
+        let mut world = world();
+        let mut stage = damage_stage();
+        let entity = world.spawn().insert(Health(15.0)).id();
+
+        send_damage(&mut world, entity, 10.0);
+        send_damage(&mut world, entity, 10.0);
+        send_damage(&mut world, entity, 10.0);
+        stage.run(&mut world);
+
+        assert_eq!(0.0, health(&world, entity));
+        assert_eq!(vec![entity], died(&world));
+    }
+
+    #[test]
+    fn dead_entities_ignore_damage() {
+        let mut world = world();
+        let mut stage = damage_stage();
+        let entity = world
+            .spawn()
+            .insert_bundle((Health(0.0), Invulnerable(0.0)))
+            .id();
+
+        send_damage(&mut world, entity, 10.0);
+        stage.run(&mut world);
+
+        assert_eq!(0.0, health(&world, entity));
+        assert_eq!(0.0, world.get::<Invulnerable>(entity).unwrap().0);
+        assert!(died(&world).is_empty());
+    }
+
+    #[test]
+    fn fall_damage_uses_fastest_impact() {
+        let mut world = world();
+        world.insert_resource(Events::<TileCollision>::default());
+        let entity = world.spawn().insert(FallDamage::default()).id();
+
+        let impacts = [
+            (TileCollisionAxis::Y, -15.0),
+            (TileCollisionAxis::Y, -20.0),
+            (TileCollisionAxis::Y, -13.0),
+            // Only landing counts as an impact
+            (TileCollisionAxis::X, -30.0),
+            (TileCollisionAxis::Y, 25.0),
+        ];
+        let mut collisions = world.get_resource_mut::<Events<TileCollision>>().unwrap();
+        for (x, &(axis, velocity)) in impacts.iter().enumerate() {
+            collisions.send(TileCollision {
+                entity,
+                entity_velocity: EntityWorldPosition::new(0.0, velocity),
+                axis,
+                tile: Tile::Stone,
+                tile_position: TileWorldPosition::new(x as i32, 0),
+            });
+        }
+
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(apply_fall_damage.system());
+        stage.run(&mut world);
+
+        let events = world.get_resource::<Events<Damage>>().unwrap();
+        let damage: Vec<_> = events
+            .get_reader()
+            .iter(events)
+            .map(|damage| (damage.entity, damage.amount, damage.source))
+            .collect();
+        let expected = FallDamage::default().damage(20.0);
+        assert_eq!(vec![(entity, expected, DamageSource::Fall)], damage);
+    }
+}
@@ -12,4 +12,12 @@ pub enum ActionInput {
     PlayerJump,
     PlayerLeft,
     PlayerRight,
+    PlayerDown,
+    PlayerMine,
+    PlayerPlace,
+    PlayerThrow,
+    HotbarNext,
+    HotbarPrevious,
+    /// Selects a hotbar slot by its index.
+    HotbarSlot(u8),
 }
@@ -22,10 +22,30 @@ impl Default for InputBindings {
         keyboard.insert(KeyCode::C, ActionInput::CycleCameraMode);
         keyboard.insert(KeyCode::A, ActionInput::PlayerLeft);
         keyboard.insert(KeyCode::D, ActionInput::PlayerRight);
+        keyboard.insert(KeyCode::S, ActionInput::PlayerDown);
         keyboard.insert(KeyCode::Space, ActionInput::PlayerJump);
+        keyboard.insert(KeyCode::G, ActionInput::PlayerThrow);
+        keyboard.insert(KeyCode::E, ActionInput::HotbarNext);
+        keyboard.insert(KeyCode::Q, ActionInput::HotbarPrevious);
+        let slot_keys = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+        for (slot, &key) in slot_keys.iter().enumerate() {
+            keyboard.insert(key, ActionInput::HotbarSlot(slot as u8));
+        }
 
         // Default mouse bindings
-        let mouse = HashMap::default();
+        let mut mouse = HashMap::default();
+        mouse.insert(MouseButton::Left, ActionInput::PlayerMine);
+        mouse.insert(MouseButton::Right, ActionInput::PlayerPlace);
 
         InputBindings { keyboard, mouse }
     }
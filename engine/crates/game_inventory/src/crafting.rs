@@ -0,0 +1,359 @@
+use crate::{Inventory, ItemRegistry, ItemStack};
+use game_lib::{
+    anyhow,
+    bevy::{
+        asset::{self as bevy_asset, AssetLoader, LoadContext, LoadedAsset},
+        prelude::*,
+        reflect::TypeUuid,
+        utils::{BoxedFuture, HashMap, HashSet},
+    },
+    derive_more::{Display, Error},
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+use game_tiles::Tile;
+
+/// A way of turning some items into other items.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(crate = "game_lib::serde")]
+pub struct Recipe {
+    pub id: String,
+
+    /// Items consumed by crafting the recipe.
+    pub inputs: Vec<ItemStack>,
+
+    /// Items produced by crafting the recipe.
+    pub outputs: Vec<ItemStack>,
+
+    /// Tile that needs to be nearby to craft the recipe, if any.
+    #[serde(default)]
+    pub station: Option<Tile>,
+}
+
+impl Recipe {
+    /// Checks whether the recipe can be crafted with the given inventory and
+    /// nearby tiles. This doesn't check whether there is room for the outputs.
+    pub fn can_craft(&self, inventory: &Inventory, nearby_tiles: &HashSet<Tile>) -> bool {
+        self.has_station(nearby_tiles) && self.has_inputs(inventory)
+    }
+
+    fn has_station(&self, nearby_tiles: &HashSet<Tile>) -> bool {
+        self.station
+            .map_or(true, |station| nearby_tiles.contains(&station))
+    }
+
+    fn has_inputs(&self, inventory: &Inventory) -> bool {
+        // The same item may be listed more than once
+        let mut required: HashMap<_, u32> = HashMap::default();
+        for input in self.inputs.iter() {
+            *required.entry(&input.item).or_default() += input.count;
+        }
+
+        required
+            .into_iter()
+            .all(|(item, count)| inventory.count(item) >= count)
+    }
+}
+
+/// Sent to craft a recipe using an entity's inventory and the tiles near it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CraftRecipe {
+    pub entity: Entity,
+    pub recipe: String,
+}
+
+/// All the recipes that can be crafted.
+#[derive(Clone, Debug, Default)]
+pub struct CraftingRegistry {
+    recipes: Vec<Recipe>,
+    ids: HashMap<String, usize>,
+}
+
+impl CraftingRegistry {
+    /// Distance in tiles that a station can be from an entity for the entity
+    /// to craft with it.
+    pub const STATION_DISTANCE: i32 = 4;
+
+    /// Registers a new recipe. Each recipe ID may only be registered once.
+    pub fn register(&mut self, recipe: Recipe) -> Result<(), RegisterRecipeError> {
+        if self.ids.contains_key(&recipe.id) {
+            return Err(RegisterRecipeError::DuplicateId(recipe.id));
+        }
+
+        if recipe.outputs.is_empty() {
+            return Err(RegisterRecipeError::NoOutputs(recipe.id));
+        }
+
+        self.ids.insert(recipe.id.clone(), self.recipes.len());
+        self.recipes.push(recipe);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Recipe> {
+        self.ids.get(id).map(|&index| &self.recipes[index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.iter()
+    }
+
+    /// Gets all the recipes which can be crafted with the given inventory and
+    /// nearby tiles, in the order they were registered.
+    pub fn craftable<'a>(
+        &'a self,
+        inventory: &'a Inventory,
+        nearby_tiles: &'a HashSet<Tile>,
+    ) -> impl Iterator<Item = &'a Recipe> + 'a {
+        self.recipes
+            .iter()
+            .filter(move |recipe| recipe.can_craft(inventory, nearby_tiles))
+    }
+
+    /// Crafts a recipe, consuming its inputs from the inventory and adding its
+    /// outputs to it. If the recipe can't be crafted, the inventory is left
+    /// unchanged.
+    pub fn craft(
+        &self,
+        items: &ItemRegistry,
+        inventory: &mut Inventory,
+        id: &str,
+        nearby_tiles: &HashSet<Tile>,
+    ) -> Result<(), CraftError> {
+        let recipe = self
+            .get(id)
+            .ok_or_else(|| CraftError::UnknownRecipe(id.into()))?;
+        if let Some(station) = recipe.station.filter(|_| !recipe.has_station(nearby_tiles)) {
+            return Err(CraftError::MissingStation(station));
+        }
+
+        // Craft on a copy of the inventory so nothing changes if it fails
+        let mut crafted = inventory.clone();
+        for input in recipe.inputs.iter() {
+            if !crafted.remove(&input.item, input.count) {
+                return Err(CraftError::MissingInputs);
+            }
+        }
+
+        for output in recipe.outputs.iter() {
+            if crafted.insert(items, output.clone()).is_some() {
+                return Err(CraftError::NotEnoughSpace);
+            }
+        }
+
+        *inventory = crafted;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Display, Error)]
+pub enum RegisterRecipeError {
+    #[display(fmt = "a recipe with the ID '{}' has already been registered", _0)]
+    DuplicateId(#[error(ignore)] String),
+
+    #[display(fmt = "the recipe '{}' has no outputs", _0)]
+    NoOutputs(#[error(ignore)] String),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+pub enum CraftError {
+    #[display(fmt = "there is no recipe with the ID '{}'", _0)]
+    UnknownRecipe(#[error(ignore)] String),
+
+    #[display(fmt = "the recipe needs to be crafted near {:?}", _0)]
+    MissingStation(#[error(ignore)] Tile),
+
+    #[display(fmt = "the inventory is missing some of the recipe's inputs")]
+    MissingInputs,
+
+    #[display(fmt = "the inventory has no room for the recipe's outputs")]
+    NotEnoughSpace,
+}
+
+/// A list of recipes loaded from an asset. Recipes are stored as a JSON array
+/// in files with the `.recipes` extension.
+#[derive(Clone, Debug, TypeUuid)]
+#[uuid = "3c5b0a1e-6a0f-4c41-9d38-2f1e6a7d4b5c"]
+pub struct RecipeList(pub Vec<Recipe>);
+
+impl RecipeList {
+    /// Parses recipes from a JSON array.
+    pub fn from_json(json: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(json).map(RecipeList)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RecipeListLoader;
+
+impl AssetLoader for RecipeListLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let recipes = RecipeList::from_json(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(recipes));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["recipes"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemDefinition;
+
+    const RECIPES: &[u8] = br#"[
+        {
+            "id": "sticks",
+            "inputs": [{ "item": "dirt", "count": 2 }],
+            "outputs": [{ "item": "stick", "count": 4 }]
+        },
+        {
+            "id": "stone",
+            "inputs": [
+                { "item": "dirt", "count": 3 },
+                { "item": "stick", "count": 1 }
+            ],
+            "outputs": [{ "item": "stone", "count": 1 }],
+            "station": "Stone"
+        }
+    ]"#;
+
+    fn items() -> ItemRegistry {
+        let mut items = ItemRegistry::default();
+        for id in ["dirt", "stick", "stone"].iter() {
+            items
+                .register(ItemDefinition::new(*id, *id, "").with_max_stack(10))
+                .unwrap();
+        }
+
+        items
+    }
+
+    fn recipes() -> CraftingRegistry {
+        let mut recipes = CraftingRegistry::default();
+        for recipe in RecipeList::from_json(RECIPES).unwrap().0 {
+            recipes.register(recipe).unwrap();
+        }
+
+        recipes
+    }
+
+    fn inventory_with(size: usize, stacks: &[(&str, u32)]) -> Inventory {
+        let mut inventory = Inventory::new(size);
+        for (slot, &(item, count)) in stacks.iter().enumerate() {
+            inventory.slots[slot] = Some(ItemStack::new(item, count));
+        }
+
+        inventory
+    }
+
+    #[test]
+    fn loads_recipes_from_json() {
+        let RecipeList(recipes) = RecipeList::from_json(RECIPES).unwrap();
+
+        let ids: Vec<_> = recipes.iter().map(|recipe| recipe.id.as_str()).collect();
+        assert_eq!(vec!["sticks", "stone"], ids);
+        assert_eq!(None, recipes[0].station);
+        assert_eq!(Some(Tile::Stone), recipes[1].station);
+    }
+
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(RecipeList::from_json(br#"[{ "id": "a" }]"#).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_recipes() {
+        let recipe = Recipe {
+            id: "a".into(),
+            inputs: Vec::new(),
+            outputs: vec![ItemStack::new("dirt", 1)],
+            station: None,
+        };
+        let mut recipes = CraftingRegistry::default();
+        recipes.register(recipe.clone()).unwrap();
+
+        assert!(matches!(
+            recipes.register(recipe.clone()),
+            Err(RegisterRecipeError::DuplicateId(_))
+        ));
+        assert!(matches!(
+            recipes.register(Recipe {
+                id: "b".into(),
+                outputs: Vec::new(),
+                ..recipe
+            }),
+            Err(RegisterRecipeError::NoOutputs(_))
+        ));
+    }
+
+    #[test]
+    fn craftable_checks_inputs_and_stations() {
+        let recipes = recipes();
+        let inventory = inventory_with(4, &[("dirt", 3), ("stick", 1)]);
+
+        let mut nearby = HashSet::default();
+        let craftable: Vec<_> = recipes
+            .craftable(&inventory, &nearby)
+            .map(|recipe| recipe.id.as_str())
+            .collect();
+        assert_eq!(vec!["sticks"], craftable);
+
+        nearby.insert(Tile::Stone);
+        let craftable: Vec<_> = recipes
+            .craftable(&inventory, &nearby)
+            .map(|recipe| recipe.id.as_str())
+            .collect();
+        assert_eq!(vec!["sticks", "stone"], craftable);
+    }
+
+    #[test]
+    fn craft_consumes_inputs_and_produces_outputs() {
+        let items = items();
+        let recipes = recipes();
+        let mut inventory = inventory_with(4, &[("dirt", 3)]);
+
+        recipes
+            .craft(&items, &mut inventory, "sticks", &HashSet::default())
+            .unwrap();
+        assert_eq!(1, inventory.count(&"dirt".into()));
+        assert_eq!(4, inventory.count(&"stick".into()));
+    }
+
+    #[test]
+    fn failed_craft_leaves_inventory_unchanged() {
+        let items = items();
+        let recipes = recipes();
+        let nearby = HashSet::default();
+
+        // Not enough inputs
+        let mut inventory = inventory_with(4, &[("dirt", 1)]);
+        let expected = inventory.clone();
+        let result = recipes.craft(&items, &mut inventory, "sticks", &nearby);
+        assert_eq!(Err(CraftError::MissingInputs), result);
+        assert_eq!(expected, inventory);
+
+        // No room for the outputs
+        let mut inventory = inventory_with(2, &[("dirt", 4), ("stone", 10)]);
+        let expected = inventory.clone();
+        let result = recipes.craft(&items, &mut inventory, "sticks", &nearby);
+        assert_eq!(Err(CraftError::NotEnoughSpace), result);
+        assert_eq!(expected, inventory);
+
+        // Missing station
+        let mut inventory = inventory_with(4, &[("dirt", 3), ("stick", 1)]);
+        let result = recipes.craft(&items, &mut inventory, "stone", &nearby);
+        assert_eq!(Err(CraftError::MissingStation(Tile::Stone)), result);
+
+        // Unknown recipe
+        let result = recipes.craft(&items, &mut inventory, "missing", &nearby);
+        assert_eq!(Err(CraftError::UnknownRecipe("missing".into())), result);
+    }
+}
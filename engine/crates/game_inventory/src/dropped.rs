@@ -0,0 +1,39 @@
+use crate::ItemStack;
+use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
+use game_tiles::EntityWorldPosition;
+
+/// A stack of items lying in the world which can be picked up.
+#[derive(Clone, PartialEq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct DroppedItem {
+    pub stack: ItemStack,
+
+    /// Seconds left before the item can be picked up. This keeps thrown items
+    /// from being picked up again immediately.
+    pub pickup_delay: f32,
+}
+
+impl DroppedItem {
+    /// Seconds after being dropped before an item can be picked up.
+    pub const PICKUP_DELAY: f32 = 1.0;
+
+    pub fn new(stack: ItemStack) -> Self {
+        DroppedItem {
+            stack,
+            pickup_delay: DroppedItem::PICKUP_DELAY,
+        }
+    }
+}
+
+/// Sent to drop a stack of items into the world, such as when a tile is mined
+/// or an item is thrown.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DropItem {
+    pub stack: ItemStack,
+
+    /// Where the center of the dropped item should be.
+    pub position: EntityWorldPosition,
+
+    /// Initial velocity of the dropped item in `m/s`.
+    pub velocity: EntityWorldPosition,
+}
@@ -0,0 +1,227 @@
+use crate::{ItemId, ItemRegistry, ItemStack};
+use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
+
+/// A fixed number of slots which can each hold a stack of items.
+#[derive(Clone, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub const DEFAULT_SIZE: usize = 36;
+
+    pub fn new(size: usize) -> Self {
+        Inventory {
+            slots: vec![None; size],
+        }
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot).and_then(Option::as_ref)
+    }
+
+    /// Adds a stack of items to the inventory, filling existing stacks of the
+    /// same item before empty slots. Returns the items that didn't fit, if
+    /// any.
+    pub fn insert(&mut self, registry: &ItemRegistry, mut stack: ItemStack) -> Option<ItemStack> {
+        let max_stack = registry.max_stack(&stack.item);
+
+        // Fill existing stacks
+        for existing in self.slots.iter_mut().flatten() {
+            if stack.count == 0 {
+                break;
+            }
+
+            if existing.item == stack.item && existing.count < max_stack {
+                let moved = stack.count.min(max_stack - existing.count);
+                existing.count += moved;
+                stack.count -= moved;
+            }
+        }
+
+        // Fill empty slots
+        for slot in self.slots.iter_mut().filter(|slot| slot.is_none()) {
+            if stack.count == 0 {
+                break;
+            }
+
+            let moved = stack.count.min(max_stack);
+            *slot = Some(ItemStack::new(stack.item.clone(), moved));
+            stack.count -= moved;
+        }
+
+        if stack.count > 0 {
+            Some(stack)
+        } else {
+            None
+        }
+    }
+
+    /// Removes up to `count` items from a slot, returning the removed items.
+    pub fn take(&mut self, slot: usize, count: u32) -> Option<ItemStack> {
+        let slot = self.slots.get_mut(slot)?;
+        let existing = slot.as_mut()?;
+        let taken = count.min(existing.count);
+        existing.count -= taken;
+        let item = existing.item.clone();
+        if existing.count == 0 {
+            *slot = None;
+        }
+
+        if taken > 0 {
+            Some(ItemStack::new(item, taken))
+        } else {
+            None
+        }
+    }
+
+    /// Counts how many of an item are in the inventory.
+    pub fn count(&self, item: &ItemId) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| &stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+
+    /// Removes `count` of an item from the inventory. Nothing is removed if
+    /// the inventory doesn't have enough of the item.
+    pub fn remove(&mut self, item: &ItemId, count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+
+        let mut remaining = count;
+        for slot in self.slots.iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+
+            if let Some(stack) = slot.as_mut().filter(|stack| &stack.item == item) {
+                let taken = remaining.min(stack.count);
+                stack.count -= taken;
+                remaining -= taken;
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory::new(Inventory::DEFAULT_SIZE)
+    }
+}
+
+/// Shows the first slots of an entity's [`Inventory`] in the hotbar.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Hotbar {
+    /// Number of inventory slots in the hotbar.
+    pub size: usize,
+
+    /// Index of the selected slot.
+    pub selected: usize,
+}
+
+impl Hotbar {
+    /// Selects the slot `offset` slots away from the currently selected one,
+    /// wrapping around at either end.
+    pub fn cycle(&mut self, offset: isize) {
+        if self.size > 0 {
+            let size = self.size as isize;
+            self.selected = (self.selected as isize + offset).rem_euclid(size) as usize;
+        }
+    }
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        Hotbar {
+            size: 9,
+            selected: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ItemDefinition;
+
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::default();
+        registry
+            .register(ItemDefinition::new("stick", "Stick", "").with_max_stack(10))
+            .unwrap();
+        registry
+            .register(ItemDefinition::new("rock", "Rock", "").with_max_stack(10))
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn insert_fills_existing_stacks_first() {
+        let registry = registry();
+        let mut inventory = Inventory::new(3);
+        inventory.slots[1] = Some(ItemStack::new("stick", 8));
+
+        assert_eq!(
+            None,
+            inventory.insert(&registry, ItemStack::new("stick", 5))
+        );
+        assert_eq!(Some(&ItemStack::new("stick", 3)), inventory.get(0));
+        assert_eq!(Some(&ItemStack::new("stick", 10)), inventory.get(1));
+        assert_eq!(13, inventory.count(&"stick".into()));
+    }
+
+    #[test]
+    fn insert_returns_leftovers() {
+        let registry = registry();
+        let mut inventory = Inventory::new(2);
+        inventory.slots[0] = Some(ItemStack::new("rock", 1));
+
+        let leftover = inventory.insert(&registry, ItemStack::new("stick", 15));
+        assert_eq!(Some(ItemStack::new("stick", 5)), leftover);
+        assert_eq!(Some(&ItemStack::new("stick", 10)), inventory.get(1));
+    }
+
+    #[test]
+    fn remove_is_all_or_nothing() {
+        let registry = registry();
+        let mut inventory = Inventory::new(3);
+        inventory.insert(&registry, ItemStack::new("stick", 15));
+
+        assert!(!inventory.remove(&"stick".into(), 16));
+        assert_eq!(15, inventory.count(&"stick".into()));
+
+        assert!(inventory.remove(&"stick".into(), 12));
+        assert_eq!(Some(&ItemStack::new("stick", 3)), inventory.get(0));
+        assert_eq!(None, inventory.get(1));
+    }
+
+    #[test]
+    fn take_clears_empty_slots() {
+        let mut inventory = Inventory::new(1);
+        inventory.slots[0] = Some(ItemStack::new("stick", 2));
+
+        assert_eq!(Some(ItemStack::new("stick", 2)), inventory.take(0, 5));
+        assert_eq!(None, inventory.get(0));
+        assert_eq!(None, inventory.take(0, 1));
+    }
+
+    #[test]
+    fn hotbar_cycle_wraps() {
+        let mut hotbar = Hotbar::default();
+        hotbar.cycle(-1);
+        assert_eq!(8, hotbar.selected);
+        hotbar.cycle(2);
+        assert_eq!(1, hotbar.selected);
+    }
+}
@@ -0,0 +1,96 @@
+use game_lib::{
+    bevy::prelude::*,
+    derive_more::{Display, From, Into},
+    serde::{Deserialize, Serialize},
+};
+use game_tiles::Tile;
+
+/// Unique identifier for a type of item, such as `"stick"`.
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Display,
+    Default,
+    Hash,
+    From,
+    Into,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+#[serde(crate = "game_lib::serde", transparent)]
+pub struct ItemId(pub String);
+
+impl ItemId {
+    pub fn new(id: impl Into<String>) -> Self {
+        ItemId(id.into())
+    }
+}
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        ItemId::new(id)
+    }
+}
+
+/// Describes a type of item.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ItemDefinition {
+    pub id: ItemId,
+
+    /// Name shown to the player.
+    pub name: String,
+
+    /// Asset path to the item's icon.
+    pub icon: String,
+
+    /// Maximum number of this item that can be in a single stack.
+    pub max_stack: u32,
+
+    /// Tile placed when using this item, if any. Mining this tile gives this
+    /// item.
+    pub tile: Option<Tile>,
+}
+
+impl ItemDefinition {
+    pub fn new(id: impl Into<ItemId>, name: impl Into<String>, icon: impl Into<String>) -> Self {
+        ItemDefinition {
+            id: id.into(),
+            name: name.into(),
+            icon: icon.into(),
+            max_stack: 99,
+            tile: None,
+        }
+    }
+
+    pub fn with_max_stack(mut self, max_stack: u32) -> Self {
+        self.max_stack = max_stack;
+        self
+    }
+
+    pub fn with_tile(mut self, tile: Tile) -> Self {
+        self.tile = Some(tile);
+        self
+    }
+}
+
+/// A number of items of the same type.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Reflect, Serialize, Deserialize)]
+#[serde(crate = "game_lib::serde")]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: impl Into<ItemId>, count: u32) -> Self {
+        ItemStack {
+            item: item.into(),
+            count,
+        }
+    }
+}
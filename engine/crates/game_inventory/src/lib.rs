@@ -0,0 +1,17 @@
+pub(crate) mod systems;
+
+mod crafting;
+mod dropped;
+mod inventory;
+mod item;
+mod plugin;
+mod registry;
+
+pub use crafting::*;
+pub use dropped::*;
+pub use inventory::*;
+pub use item::*;
+pub use plugin::*;
+pub use registry::*;
+
+game_lib::fix_bevy_derive!(game_lib::bevy);
@@ -0,0 +1,91 @@
+use crate::{
+    CraftRecipe, DropItem, DroppedItem, Hotbar, Inventory, ItemId, ItemRegistry, ItemStack,
+    RecipeList, RecipeListLoader,
+};
+use game_core::{loading::MainLoadingMode, modes::ModeExt, GameStage, GlobalMode, ModeEvent};
+use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.register_type::<ItemId>()
+            .register_type::<ItemStack>()
+            .register_type::<Inventory>()
+            .register_type::<Hotbar>()
+            .register_type::<DroppedItem>()
+            .add_event::<DropItem>()
+            .add_event::<CraftRecipe>()
+            .add_asset::<RecipeList>()
+            .init_asset_loader::<RecipeListLoader>()
+            .insert_resource(ItemRegistry::builtin())
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .label(InventorySystem::RequestRecipes)
+                    .in_ambiguity_set(MainLoadingMode::RequestAssets)
+                    .with_run_criteria(Some(MainLoadingMode::RequestAssets).on(ModeEvent::Enter))
+                    .with_system(crate::systems::request_recipes.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GamePreUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Enter))
+                    .with_system(crate::systems::setup_hotbar.system())
+                    .with_system(crate::systems::setup_crafting.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GamePostUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Exit))
+                    .with_system(crate::systems::cleanup_hotbar.system())
+                    .with_system(crate::systems::cleanup_crafting_ui.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .label(InventorySystem::ClickRecipes)
+                    .before(InventorySystem::Craft)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::click_recipes.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .label(InventorySystem::Craft)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::craft_recipes.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GamePostUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .label(InventorySystem::UpdateHotbar)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::update_hotbar.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GamePostUpdate,
+                SystemSet::new()
+                    .label(InventoryPlugin)
+                    .label(InventorySystem::UpdateCraftingUi)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::update_crafting_ui.system()),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub enum InventorySystem {
+    RequestRecipes,
+    ClickRecipes,
+    Craft,
+    UpdateHotbar,
+    UpdateCraftingUi,
+}
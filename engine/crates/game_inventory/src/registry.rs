@@ -0,0 +1,116 @@
+use crate::{ItemDefinition, ItemId};
+use game_lib::{
+    bevy::utils::HashMap,
+    derive_more::{Display, Error},
+};
+use game_tiles::Tile;
+
+/// All the types of items that exist.
+#[derive(Clone, Debug, Default)]
+pub struct ItemRegistry {
+    items: HashMap<ItemId, ItemDefinition>,
+    tiles: HashMap<Tile, ItemId>,
+}
+
+impl ItemRegistry {
+    /// Creates a registry containing the items built into the game.
+    pub fn builtin() -> Self {
+        let mut registry = ItemRegistry::default();
+        let items = vec![
+            ItemDefinition::new("dirt", "Dirt", "sprites/tiles/dirt.png").with_tile(Tile::Dirt),
+            ItemDefinition::new("stone", "Stone", "sprites/tiles/stone.png").with_tile(Tile::Stone),
+            ItemDefinition::new("stick", "Stick", "sprites/items/stick.png"),
+        ];
+        for item in items {
+            registry
+                .register(item)
+                .expect("built in items should be unique");
+        }
+
+        registry
+    }
+
+    /// Registers a new type of item. Each item ID and tile may only be
+    /// registered once, and every item must fit at least one in a stack.
+    pub fn register(&mut self, definition: ItemDefinition) -> Result<(), RegisterItemError> {
+        if definition.max_stack == 0 {
+            return Err(RegisterItemError::ZeroMaxStack(definition.id));
+        }
+
+        if self.items.contains_key(&definition.id) {
+            return Err(RegisterItemError::DuplicateId(definition.id));
+        }
+
+        if let Some(tile) = definition.tile {
+            if self.tiles.contains_key(&tile) {
+                return Err(RegisterItemError::DuplicateTile(tile));
+            }
+
+            self.tiles.insert(tile, definition.id.clone());
+        }
+
+        self.items.insert(definition.id.clone(), definition);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &ItemId) -> Option<&ItemDefinition> {
+        self.items.get(id)
+    }
+
+    /// Gets the item which places the given tile.
+    pub fn get_by_tile(&self, tile: Tile) -> Option<&ItemDefinition> {
+        self.tiles.get(&tile).and_then(|id| self.items.get(id))
+    }
+
+    /// Gets the maximum stack size of an item. Unknown items can't be
+    /// stacked.
+    pub fn max_stack(&self, id: &ItemId) -> u32 {
+        self.get(id).map_or(1, |definition| definition.max_stack)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDefinition> {
+        self.items.values()
+    }
+}
+
+#[derive(Clone, Debug, Display, Error)]
+pub enum RegisterItemError {
+    #[display(fmt = "an item with the ID '{}' has already been registered", _0)]
+    DuplicateId(#[error(ignore)] ItemId),
+
+    #[display(fmt = "an item which places {:?} has already been registered", _0)]
+    DuplicateTile(#[error(ignore)] Tile),
+
+    #[display(fmt = "the item with the ID '{}' has a max stack size of 0", _0)]
+    ZeroMaxStack(#[error(ignore)] ItemId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_rejects_duplicates() {
+        let mut registry = ItemRegistry::default();
+        registry
+            .register(ItemDefinition::new("dirt", "Dirt", "").with_tile(Tile::Dirt))
+            .unwrap();
+
+        let result = registry.register(ItemDefinition::new("dirt", "Other Dirt", ""));
+        assert!(matches!(result, Err(RegisterItemError::DuplicateId(_))));
+        let result = registry.register(ItemDefinition::new("mud", "Mud", "").with_tile(Tile::Dirt));
+        assert!(matches!(
+            result,
+            Err(RegisterItemError::DuplicateTile(Tile::Dirt))
+        ));
+    }
+
+    #[test]
+    fn register_rejects_empty_stacks() {
+        let mut registry = ItemRegistry::default();
+        let result = registry.register(ItemDefinition::new("air", "Air", "").with_max_stack(0));
+
+        assert!(matches!(result, Err(RegisterItemError::ZeroMaxStack(_))));
+        assert!(registry.get(&"air".into()).is_none());
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{
+    CraftRecipe, CraftingRegistry, Hotbar, Inventory, ItemId, ItemRegistry, Recipe, RecipeList,
+};
+use game_core::loading::RequiredAssetLoader;
+use game_lib::{
+    bevy::{
+        prelude::*,
+        utils::{HashMap, HashSet},
+    },
+    tracing::{self, instrument},
+};
+use game_tiles::{EntityWorldRect, GameWorld, Tile, TileWorldRect};
+
+/// Handle to the recipes loaded while the game is loading.
+pub struct RecipeListHandle(Handle<RecipeList>);
+
+#[instrument(skip(commands, asset_loader))]
+pub fn request_recipes(mut commands: Commands, mut asset_loader: RequiredAssetLoader) {
+    let handle = asset_loader.load_required("data/default.recipes");
+    commands.insert_resource(RecipeListHandle(handle));
+}
+
+#[instrument(skip(commands, handle, recipe_lists, items))]
+pub fn setup_crafting(
+    mut commands: Commands,
+    handle: Res<RecipeListHandle>,
+    recipe_lists: Res<Assets<RecipeList>>,
+    items: Res<ItemRegistry>,
+) {
+    let mut crafting = CraftingRegistry::default();
+    let recipes = match recipe_lists.get(&handle.0) {
+        Some(list) => list.0.as_slice(),
+        None => {
+            warn!("recipes failed to load");
+            &[]
+        }
+    };
+    for recipe in recipes.iter().cloned() {
+        // Skip recipes which use items that don't exist
+        let unknown_item = recipe
+            .inputs
+            .iter()
+            .chain(recipe.outputs.iter())
+            .find(|stack| items.get(&stack.item).is_none());
+        if let Some(stack) = unknown_item {
+            warn!(
+                "skipping recipe '{}' with unknown item '{}'",
+                recipe.id, stack.item
+            );
+            continue;
+        }
+
+        if let Err(error) = crafting.register(recipe) {
+            warn!("skipping recipe: {}", error);
+        }
+    }
+
+    commands.insert_resource(crafting);
+}
+
+#[instrument(skip(events, crafting, items, world, query))]
+pub fn craft_recipes(
+    mut events: EventReader<CraftRecipe>,
+    crafting: Res<CraftingRegistry>,
+    items: Res<ItemRegistry>,
+    world: Res<GameWorld>,
+    mut query: Query<(&EntityWorldRect, &mut Inventory)>,
+) {
+    for event in events.iter() {
+        let (&bounds, mut inventory) = match query.get_mut(event.entity) {
+            Ok(crafter) => crafter,
+            Err(_) => continue,
+        };
+
+        let nearby_tiles = nearby_tiles(&world, bounds);
+        if let Err(error) = crafting.craft(&items, &mut inventory, &event.recipe, &nearby_tiles) {
+            debug!("failed to craft '{}': {}", event.recipe, error);
+        }
+    }
+}
+
+/// Gets the tiles near an entity which it can use as crafting stations.
+fn nearby_tiles(world: &GameWorld, bounds: EntityWorldRect) -> HashSet<Tile> {
+    let area = TileWorldRect::from(bounds).expand(CraftingRegistry::STATION_DISTANCE);
+    area.iter_positions()
+        .filter_map(|position| world.get_tile(position).ok().copied().flatten())
+        .collect()
+}
+
+/// Marks the root node of the crafting UI.
+pub struct CraftingUi;
+
+/// A button in the crafting UI which crafts a recipe when clicked.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecipeButton {
+    pub entity: Entity,
+    pub recipe: String,
+}
+
+#[instrument(skip(craft_recipe, query))]
+pub fn click_recipes(
+    mut craft_recipe: EventWriter<CraftRecipe>,
+    query: Query<(&Interaction, &RecipeButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            craft_recipe.send(CraftRecipe {
+                entity: button.entity,
+                recipe: button.recipe.clone(),
+            });
+        }
+    }
+}
+
+#[instrument(skip(commands, query))]
+pub fn cleanup_crafting_ui(mut commands: Commands, query: Query<Entity, With<CraftingUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[instrument(skip(
+    commands,
+    style,
+    crafting,
+    items,
+    world,
+    crafter_query,
+    ui_query,
+    shown
+))]
+pub fn update_crafting_ui(
+    mut commands: Commands,
+    style: Res<HotbarStyle>,
+    crafting: Res<CraftingRegistry>,
+    items: Res<ItemRegistry>,
+    world: Res<GameWorld>,
+    crafter_query: Query<(Entity, &EntityWorldRect, &Inventory), With<Hotbar>>,
+    ui_query: Query<Entity, With<CraftingUi>>,
+    mut shown: Local<Vec<String>>,
+) {
+    let (entity, &bounds, inventory) = match crafter_query.iter().next() {
+        Some(crafter) => crafter,
+        None => return,
+    };
+
+    // Only rebuild the UI when the craftable recipes change
+    let nearby_tiles = nearby_tiles(&world, bounds);
+    let craftable: Vec<_> = crafting.craftable(inventory, &nearby_tiles).collect();
+    let unchanged = shown.iter().eq(craftable.iter().map(|recipe| &recipe.id));
+    if unchanged && ui_query.iter().next().is_some() {
+        return;
+    }
+
+    *shown = craftable.iter().map(|recipe| recipe.id.clone()).collect();
+    for entity in ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut root = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                ..Default::default()
+            },
+            flex_direction: FlexDirection::ColumnReverse,
+            ..Default::default()
+        },
+        material: style.background.clone(),
+        ..Default::default()
+    });
+    root.insert(CraftingUi).with_children(|root| {
+        for recipe in craftable {
+            root.spawn_bundle(ButtonBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(2.0)),
+                    padding: Rect::all(Val::Px(4.0)),
+                    ..Default::default()
+                },
+                material: style.slot.clone(),
+                ..Default::default()
+            })
+            .insert(RecipeButton {
+                entity,
+                recipe: recipe.id.clone(),
+            })
+            .with_children(|button| {
+                button.spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        recipe_label(&items, recipe),
+                        TextStyle {
+                            font: style.font.clone(),
+                            font_size: 14.0,
+                            color: Color::WHITE,
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                });
+            });
+        }
+    });
+}
+
+/// Describes what a recipe produces, such as `"4 Stick"`.
+fn recipe_label(items: &ItemRegistry, recipe: &Recipe) -> String {
+    recipe
+        .outputs
+        .iter()
+        .map(|stack| {
+            let name = items
+                .get(&stack.item)
+                .map_or(stack.item.0.as_str(), |item| item.name.as_str());
+            format!("{} {}", stack.count, name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Marks the root node of the hotbar UI.
+pub struct HotbarUi;
+
+/// Assets used to draw the hotbar and crafting UI.
+pub struct HotbarStyle {
+    font: Handle<Font>,
+    background: Handle<ColorMaterial>,
+    slot: Handle<ColorMaterial>,
+    selected_slot: Handle<ColorMaterial>,
+    icons: HashMap<ItemId, Handle<ColorMaterial>>,
+}
+
+#[instrument(skip(commands, asset_server, materials))]
+pub fn setup_hotbar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(HotbarStyle {
+        font: asset_server.load("fonts/selawik/selawk.ttf"),
+        background: materials.add(Color::NONE.into()),
+        slot: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.6).into()),
+        selected_slot: materials.add(Color::rgba(0.8, 0.8, 0.8, 0.6).into()),
+        icons: HashMap::default(),
+    });
+}
+
+#[instrument(skip(commands, query))]
+pub fn cleanup_hotbar(mut commands: Commands, query: Query<Entity, With<HotbarUi>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<HotbarStyle>();
+}
+
+#[instrument(skip(
+    commands,
+    asset_server,
+    materials,
+    style,
+    registry,
+    hotbar_query,
+    ui_query
+))]
+pub fn update_hotbar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut style: ResMut<HotbarStyle>,
+    registry: Res<ItemRegistry>,
+    hotbar_query: Query<(&Inventory, &Hotbar), Or<(Changed<Inventory>, Changed<Hotbar>)>>,
+    ui_query: Query<Entity, With<HotbarUi>>,
+) {
+    let (inventory, hotbar) = match hotbar_query.iter().next() {
+        Some(hotbar) => hotbar,
+        None => return,
+    };
+
+    // Rebuild the hotbar from scratch since it rarely changes
+    for entity in ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let style = &mut *style;
+    let mut root = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                bottom: Val::Px(8.0),
+                left: Val::Px(0.0),
+                ..Default::default()
+            },
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        material: style.background.clone(),
+        ..Default::default()
+    });
+    root.insert(HotbarUi).with_children(|root| {
+        for slot in 0..hotbar.size {
+            let material = if slot == hotbar.selected {
+                style.selected_slot.clone()
+            } else {
+                style.slot.clone()
+            };
+
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                    margin: Rect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material,
+                ..Default::default()
+            })
+            .with_children(|node| {
+                let stack = match inventory.get(slot) {
+                    Some(stack) => stack,
+                    None => return,
+                };
+
+                // Icon
+                if let Some(definition) = registry.get(&stack.item) {
+                    let icon = style
+                        .icons
+                        .entry(stack.item.clone())
+                        .or_insert_with(|| {
+                            let texture: Handle<Texture> =
+                                asset_server.load(definition.icon.as_str());
+                            materials.add(texture.into())
+                        })
+                        .clone();
+                    node.spawn_bundle(ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(32.0), Val::Px(32.0)),
+                            ..Default::default()
+                        },
+                        material: icon,
+                        ..Default::default()
+                    });
+                }
+
+                // Stack size
+                if stack.count > 1 {
+                    node.spawn_bundle(TextBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: Rect {
+                                bottom: Val::Px(2.0),
+                                right: Val::Px(4.0),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        text: Text::with_section(
+                            stack.count.to_string(),
+                            TextStyle {
+                                font: style.font.clone(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                            Default::default(),
+                        ),
+                        ..Default::default()
+                    });
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ItemDefinition, ItemStack};
+    use game_tiles::generation::FlatWorldGenerator;
+
+    #[test]
+    fn clicking_recipe_crafts_it() {
+        let mut items = ItemRegistry::default();
+        for id in ["dirt", "stick"].iter() {
+            items
+                .register(ItemDefinition::new(*id, *id, "").with_max_stack(10))
+                .unwrap();
+        }
+
+        let mut crafting = CraftingRegistry::default();
+        crafting
+            .register(Recipe {
+                id: "sticks".into(),
+                inputs: vec![ItemStack::new("dirt", 2)],
+                outputs: vec![ItemStack::new("stick", 4)],
+                station: None,
+            })
+            .unwrap();
+
+        let mut world = World::default();
+        world.insert_resource(items);
+        world.insert_resource(crafting);
+        world.insert_resource(GameWorld::new(Box::new(FlatWorldGenerator::new(
+            Tile::Stone,
+            None,
+        ))));
+        world.insert_resource(Events::<CraftRecipe>::default());
+
+        let mut inventory = Inventory::new(4);
+        inventory.slots[0] = Some(ItemStack::new("dirt", 3));
+        let crafter = world
+            .spawn()
+            .insert_bundle((EntityWorldRect::default(), inventory))
+            .id();
+        let button = RecipeButton {
+            entity: crafter,
+            recipe: "sticks".into(),
+        };
+        world
+            .spawn()
+            .insert_bundle((Interaction::Clicked, button.clone()));
+        world.spawn().insert_bundle((Interaction::Hovered, button));
+
+        let mut stage = SystemStage::single_threaded();
+        stage
+            .add_system(click_recipes.system().label("click"))
+            .add_system(craft_recipes.system().after("click"));
+
+        // Only the clicked button crafts, and only once per click
+        for _ in 0..2 {
+            stage.run(&mut world);
+            let inventory = world.get::<Inventory>(crafter).unwrap();
+            assert_eq!(1, inventory.count(&"dirt".into()));
+            assert_eq!(4, inventory.count(&"stick".into()));
+        }
+    }
+}
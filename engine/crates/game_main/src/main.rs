@@ -1,7 +1,9 @@
 pub use game_camera as camera;
 pub use game_controller as controller;
 pub use game_core as core;
+pub use game_health as health;
 pub use game_input as input;
+pub use game_inventory as inventory;
 pub use game_physics as physics;
 pub use game_tiles as tiles;
 pub use game_wasi as wasi;
@@ -36,13 +38,16 @@ fn main() {
         .add_plugin(crate::tiles::TilePlugin)
         .add_plugin(crate::camera::CameraPlugin)
         .add_plugin(crate::physics::PhysicsPlugin)
+        .add_plugin(crate::health::HealthPlugin)
         .add_plugin(crate::input::InputPlugin)
+        .add_plugin(crate::inventory::InventoryPlugin)
         .add_plugin(crate::controller::ControllerPlugin)
         .add_plugin(crate::plugins::PlayerPlugin)
         .add_plugin(crate::plugins::ConfigPlugin)
         .add_plugin(crate::plugins::DebugPlugin)
+        .add_plugin(crate::plugins::DroppedItemPlugin)
         .add_plugin(crate::plugins::TimedPlugin)
-        // .add_plugin(crate::wasi::WasmPlugin)
+        .add_plugin(crate::wasi::WasmPlugin)
         .insert_resource(PhysicsState {
             // drag: Drag::from_terminal_velocity(10.0, 62.0, 98.1),
             drag: Drag::from_terminal_velocity(300.0, 62.0, 9.81),
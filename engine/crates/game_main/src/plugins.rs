@@ -1,9 +1,11 @@
 pub mod config;
 pub mod debug;
+pub mod dropped_items;
 pub mod player;
 pub mod timed;
 
 pub use config::ConfigPlugin;
 pub use debug::DebugPlugin;
+pub use dropped_items::DroppedItemPlugin;
 pub use player::PlayerPlugin;
 pub use timed::TimedPlugin;
@@ -3,12 +3,14 @@ use game_lib::bevy::prelude::*;
 #[derive(Debug, Reflect)]
 pub struct DebugConfig {
     pub enable_teleporting: bool,
+    pub enable_spawning: bool,
 }
 
 impl Default for DebugConfig {
     fn default() -> Self {
         DebugConfig {
             enable_teleporting: false,
+            enable_spawning: false,
         }
     }
 }
@@ -172,6 +172,10 @@ impl DebugPlugin {
         if input.just_released(KeyCode::F1) {
             config.enable_teleporting = !config.enable_teleporting;
         }
+
+        if input.just_released(KeyCode::F2) {
+            config.enable_spawning = !config.enable_spawning;
+        }
     }
 
     #[instrument(skip(config, input, cursor_state, player_query))]
@@ -192,14 +196,15 @@ impl DebugPlugin {
         }
     }
 
-    #[instrument(skip(commands, materials, input, cursor_state))]
+    #[instrument(skip(commands, config, materials, input, cursor_state))]
     fn spawn_on_click(
         mut commands: Commands,
+        config: Res<DebugConfig>,
         mut materials: ResMut<Assets<ColorMaterial>>,
         input: Res<Input<MouseButton>>,
         cursor_state: Res<CursorState>,
     ) {
-        if input.pressed(MouseButton::Right) {
+        if config.enable_spawning && input.pressed(MouseButton::Right) {
             let size = Vec2::new(0.1, 0.1);
             commands
                 .spawn_bundle(SpriteBundle {
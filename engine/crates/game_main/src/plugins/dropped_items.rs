@@ -0,0 +1,238 @@
+use crate::{
+    controller::{ControllerSystem, Player},
+    inventory::{DropItem, DroppedItem, Inventory, ItemId, ItemRegistry},
+    physics::{JumpStatus, PhysicsBundle, PhysicsPlugin, Velocity},
+    plugins::timed::Timed,
+};
+use game_core::{modes::ModeExt, GameStage, GlobalMode, ModeEvent};
+use game_lib::{
+    bevy::{ecs as bevy_ecs, prelude::*, utils::HashMap},
+    tracing::{self, instrument},
+};
+use game_tiles::{EntityWorldPosition, EntityWorldRect};
+use std::time::Duration;
+
+/// Marks the sprite of a dropped item, which bobs up and down while the item
+/// is on the ground.
+struct Bobbing;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub struct DroppedItemPlugin;
+
+impl DroppedItemPlugin {
+    /// How long dropped items stay in the world before despawning.
+    const LIFETIME: Duration = Duration::from_secs(300);
+
+    /// Size of dropped items in `m`.
+    const SIZE: f32 = 0.5;
+
+    /// Maximum distance in `m` between the centers of two dropped items for
+    /// them to be merged together.
+    const MERGE_DISTANCE: f32 = 1.0;
+
+    #[instrument(skip(commands, asset_server, materials, registry, icons, events))]
+    fn spawn_dropped_items(
+        mut commands: Commands,
+        asset_server: Res<AssetServer>,
+        mut materials: ResMut<Assets<ColorMaterial>>,
+        registry: Res<ItemRegistry>,
+        mut icons: Local<HashMap<ItemId, Handle<ColorMaterial>>>,
+        mut events: EventReader<DropItem>,
+    ) {
+        for event in events.iter() {
+            let definition = match registry.get(&event.stack.item) {
+                Some(definition) => definition,
+                None => {
+                    warn!("tried to drop unknown item '{}'", event.stack.item);
+                    continue;
+                }
+            };
+            let icon = icons
+                .entry(definition.id.clone())
+                .or_insert_with(|| {
+                    let texture: Handle<Texture> = asset_server.load(definition.icon.as_str());
+                    materials.add(texture.into())
+                })
+                .clone();
+
+            let size = Vec2::new(Self::SIZE, Self::SIZE);
+            commands
+                .spawn_bundle(PhysicsBundle {
+                    bounds: EntityWorldRect::from_center(event.position, (size / 2.0).into()),
+                    velocity: Velocity(event.velocity),
+                    ..Default::default()
+                })
+                .insert(Transform::from_translation(
+                    Vec2::from(event.position).extend(0.5),
+                ))
+                .insert(GlobalTransform::default())
+                .insert(DroppedItem::new(event.stack.clone()))
+                .insert(Timed::new(Self::LIFETIME))
+                .with_children(|item| {
+                    item.spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            size,
+                            ..Default::default()
+                        },
+                        material: icon,
+                        ..Default::default()
+                    })
+                    .insert(Bobbing);
+                });
+        }
+    }
+
+    #[instrument(skip(time, items, sprites))]
+    fn bob_dropped_items(
+        time: Res<Time>,
+        items: Query<(&JumpStatus, &Children), With<DroppedItem>>,
+        mut sprites: Query<&mut Transform, With<Bobbing>>,
+    ) {
+        const AMPLITUDE: f32 = 0.05;
+        const FREQUENCY: f32 = 2.0;
+        let offset = AMPLITUDE * (time.seconds_since_startup() as f32 * FREQUENCY).sin();
+        for (&jump_status, children) in items.iter() {
+            let offset = if jump_status == JumpStatus::OnGround {
+                offset + AMPLITUDE
+            } else {
+                0.0
+            };
+
+            for &child in children.iter() {
+                if let Ok(mut transform) = sprites.get_mut(child) {
+                    transform.translation.y = offset;
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(commands, registry, query))]
+    fn merge_dropped_items(
+        mut commands: Commands,
+        registry: Res<ItemRegistry>,
+        mut query: Query<(Entity, &EntityWorldRect, &mut DroppedItem, &mut Timed)>,
+    ) {
+        // Find which stacks to merge together
+        let mut items: Vec<_> = query
+            .iter_mut()
+            .filter(|(_, _, dropped, _)| dropped.stack.count > 0)
+            .map(|(entity, bounds, dropped, _)| {
+                let center = Vec2::from(bounds.center());
+                (
+                    entity,
+                    center,
+                    dropped.stack.item.clone(),
+                    dropped.stack.count,
+                )
+            })
+            .collect();
+        items.sort_by_key(|&(entity, ..)| entity);
+
+        let mut merged = HashMap::default();
+        for target in 0..items.len() {
+            let max_stack = registry.max_stack(&items[target].2);
+            for source in target + 1..items.len() {
+                let (_, target_center, ref target_item, target_count) = items[target];
+                let (_, source_center, ref source_item, source_count) = items[source];
+                let can_merge = target_count > 0
+                    && source_count > 0
+                    && target_item == source_item
+                    && target_count + source_count <= max_stack
+                    && target_center.distance(source_center) <= Self::MERGE_DISTANCE;
+                if can_merge {
+                    items[target].3 += source_count;
+                    items[source].3 = 0;
+                    merged.insert(items[target].0, items[target].3);
+                    merged.insert(items[source].0, 0);
+                }
+            }
+        }
+
+        // Update the stacks, despawning stacks that were merged into others
+        for (entity, count) in merged {
+            if let Ok((_, _, mut dropped, mut timed)) = query.get_mut(entity) {
+                dropped.stack.count = count;
+                if count == 0 {
+                    commands.entity(entity).despawn_recursive();
+                } else {
+                    *timed = Timed::new(Self::LIFETIME);
+                }
+            }
+        }
+    }
+
+    #[instrument(skip(commands, time, registry, players, items))]
+    fn pick_up_dropped_items(
+        mut commands: Commands,
+        time: Res<Time>,
+        registry: Res<ItemRegistry>,
+        mut players: Query<(&EntityWorldRect, &mut Inventory), With<Player>>,
+        mut items: Query<(Entity, &EntityWorldRect, &mut DroppedItem)>,
+    ) {
+        for (entity, &item_bounds, mut dropped) in items.iter_mut() {
+            if dropped.pickup_delay > 0.0 {
+                dropped.pickup_delay -= time.delta_seconds();
+                continue;
+            }
+
+            // Items emptied by merging are about to be despawned
+            if dropped.stack.count == 0 {
+                continue;
+            }
+
+            for (&player_bounds, mut inventory) in players.iter_mut() {
+                if !player_bounds.intersects(item_bounds) {
+                    continue;
+                }
+
+                match inventory.insert(&registry, dropped.stack.clone()) {
+                    Some(leftover) => {
+                        if leftover != dropped.stack {
+                            dropped.stack = leftover;
+                        }
+                    }
+                    None => {
+                        dropped.stack.count = 0;
+                        commands.entity(entity).despawn_recursive();
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Plugin for DroppedItemPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set_to_stage(
+            GameStage::GameUpdate,
+            SystemSet::new()
+                .label(DroppedItemPlugin)
+                .label(DroppedItemSystem::Spawn)
+                .after(ControllerSystem::Interact)
+                .before(PhysicsPlugin)
+                .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                .with_system(Self::spawn_dropped_items.system()),
+        )
+        .add_system_set_to_stage(
+            GameStage::GameUpdate,
+            SystemSet::new()
+                .label(DroppedItemPlugin)
+                .label(DroppedItemSystem::Update)
+                .after(PhysicsPlugin)
+                .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                .with_system(Self::bob_dropped_items.system())
+                .with_system(
+                    Self::merge_dropped_items
+                        .system()
+                        .chain(Self::pick_up_dropped_items.system()),
+                ),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub enum DroppedItemSystem {
+    Spawn,
+    Update,
+}
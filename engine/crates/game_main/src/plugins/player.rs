@@ -1,11 +1,16 @@
-use crate::physics::{Mass, PhysicsBundle};
-use game_controller::{Player, PlayerBundle};
+use crate::physics::{Mass, PhysicsBundle, PhysicsPlugin, PreviousBounds};
+use game_controller::{Player, PlayerBundle, RespawnPoint};
 use game_core::{modes::ModeExt, GameStage, GlobalMode, ModeEvent};
 use game_lib::{
     bevy::{ecs as bevy_ecs, prelude::*},
     tracing::{self, instrument},
 };
-use game_tiles::{EntityWorldPosition, EntityWorldRect};
+use game_tiles::{EntityWorldPosition, EntityWorldRect, GameWorld};
+
+/// Marks a player which still needs to be moved to the world's spawn point.
+/// The world doesn't exist yet when players are added, so they are moved once
+/// it does.
+struct PendingSpawn;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
 pub struct PlayerPlugin;
@@ -14,25 +19,61 @@ impl PlayerPlugin {
     #[instrument(skip(commands, materials))]
     fn add_player(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
         let size = Vec2::new(1.6, 2.9);
-        commands.spawn_bundle(PlayerBundle {
-            sprite_bundle: SpriteBundle {
-                sprite: Sprite {
-                    size,
+        commands
+            .spawn_bundle(PlayerBundle {
+                sprite_bundle: SpriteBundle {
+                    sprite: Sprite {
+                        size,
+                        ..Default::default()
+                    },
+                    material: materials.add(ColorMaterial::color(Color::BLUE)),
+                    ..Default::default()
+                },
+                physics_bundle: PhysicsBundle {
+                    bounds: EntityWorldRect::from_center(
+                        EntityWorldPosition::new(0.0, 0.0),
+                        (size / 2.0).into(),
+                    ),
+                    mass: Mass(62.0),
                     ..Default::default()
                 },
-                material: materials.add(ColorMaterial::color(Color::BLUE)),
-                ..Default::default()
-            },
-            physics_bundle: PhysicsBundle {
-                bounds: EntityWorldRect::from_center(
-                    EntityWorldPosition::new(0.0, 0.0),
-                    (size / 2.0).into(),
-                ),
-                mass: Mass(62.0),
                 ..Default::default()
-            },
-            ..Default::default()
-        });
+            })
+            .insert(PendingSpawn);
+    }
+
+    #[instrument(skip(commands, world, query))]
+    fn move_to_spawn(
+        mut commands: Commands,
+        world: Option<ResMut<GameWorld>>,
+        mut query: Query<
+            (
+                Entity,
+                &mut EntityWorldRect,
+                &mut PreviousBounds,
+                &mut RespawnPoint,
+            ),
+            With<PendingSpawn>,
+        >,
+    ) {
+        let mut world = match world {
+            Some(world) => world,
+            None => return,
+        };
+
+        for (entity, mut bounds, mut previous_bounds, mut respawn_point) in query.iter_mut() {
+            // Try again next frame if there's no room to spawn yet
+            *bounds = match world.get_spawn_bounds(bounds.size()) {
+                Some(spawn_bounds) => spawn_bounds,
+                None => {
+                    warn!("no room to spawn the player");
+                    continue;
+                }
+            };
+            previous_bounds.0 = None;
+            respawn_point.0 = bounds.center();
+            commands.entity(entity).remove::<PendingSpawn>();
+        }
     }
 
     #[instrument(skip(commands, players))]
@@ -52,6 +93,15 @@ impl Plugin for PlayerPlugin {
                 .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Enter))
                 .with_system(Self::add_player.system()),
         )
+        .add_system_set_to_stage(
+            GameStage::GameUpdate,
+            SystemSet::new()
+                .label(PlayerPlugin)
+                .label(PlayerSystem::MoveToSpawn)
+                .before(PhysicsPlugin)
+                .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                .with_system(Self::move_to_spawn.system()),
+        )
         .add_system_set_to_stage(
             GameStage::GamePostUpdate,
             SystemSet::new()
@@ -61,3 +111,8 @@ impl Plugin for PlayerPlugin {
         );
     }
 }
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, SystemLabel)]
+pub enum PlayerSystem {
+    MoveToSpawn,
+}
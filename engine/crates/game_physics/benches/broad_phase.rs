@@ -0,0 +1,121 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use game_lib::bevy::math::Vec2;
+use game_physics::{
+    bodies::AxisAlignedBoundingBox,
+    broad_phase::{BroadPhase, Entry, QuadTree, SpatialHash},
+};
+
+/// Size of the area bodies are scattered over.
+const AREA: f32 = 512.0;
+
+/// A body bouncing around inside the area.
+#[derive(Clone, Copy, Debug)]
+struct Body {
+    position: Vec2,
+    velocity: Vec2,
+}
+
+impl Body {
+    fn bounds(self) -> AxisAlignedBoundingBox {
+        AxisAlignedBoundingBox::new(self.position, Vec2::new(1.0, 2.0))
+    }
+
+    fn step(&mut self) {
+        self.position += self.velocity;
+        if self.position.x < -AREA / 2.0 || self.position.x > AREA / 2.0 {
+            self.velocity.x = -self.velocity.x;
+        }
+        if self.position.y < -AREA / 2.0 || self.position.y > AREA / 2.0 {
+            self.velocity.y = -self.velocity.y;
+        }
+    }
+}
+
+/// Scatters bodies over the area using a simple deterministic generator.
+fn bodies(count: usize) -> Vec<Body> {
+    let mut seed = 0x2545_f491_u32;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+
+    (0..count)
+        .map(|_| Body {
+            position: Vec2::new(next() - 0.5, next() - 0.5) * AREA,
+            velocity: Vec2::new(next() - 0.5, next() - 0.5) * 0.5,
+        })
+        .collect()
+}
+
+/// The operations needed to index moving bodies.
+trait BodyIndex: for<'a> BroadPhase<'a, Id = Entry> {
+    fn insert(&mut self, bounds: AxisAlignedBoundingBox) -> Entry;
+    fn set_bounds(&mut self, entry: Entry, bounds: AxisAlignedBoundingBox);
+}
+
+impl BodyIndex for QuadTree<(), 4, 16, 8> {
+    fn insert(&mut self, bounds: AxisAlignedBoundingBox) -> Entry {
+        QuadTree::insert(self, (), bounds)
+    }
+
+    fn set_bounds(&mut self, entry: Entry, bounds: AxisAlignedBoundingBox) {
+        QuadTree::set_bounds(self, entry, bounds);
+    }
+}
+
+impl BodyIndex for SpatialHash<()> {
+    fn insert(&mut self, bounds: AxisAlignedBoundingBox) -> Entry {
+        SpatialHash::insert(self, (), bounds)
+    }
+
+    fn set_bounds(&mut self, entry: Entry, bounds: AxisAlignedBoundingBox) {
+        SpatialHash::set_bounds(self, entry, bounds);
+    }
+}
+
+/// Moves every body, updates the index, and queries around every body.
+fn simulate<I: BodyIndex>(index: &mut I, bodies: &mut [Body], entries: &[Entry]) -> usize {
+    let mut found = 0;
+    for (body, &entry) in bodies.iter_mut().zip(entries) {
+        body.step();
+        index.set_bounds(entry, body.bounds());
+    }
+
+    for body in bodies.iter() {
+        let query = AxisAlignedBoundingBox::from_center(body.position, Vec2::ONE * 4.0);
+        found += index.query_bounds(query).into_iter().count();
+    }
+
+    found
+}
+
+fn bench_index<I: BodyIndex>(c: &mut Criterion, name: &str, create: impl Fn() -> I) {
+    let mut group = c.benchmark_group(format!("broad_phase/{}", name));
+    for &count in [100, 1000, 5000].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            let mut index = create();
+            let mut bodies = bodies(count);
+            let entries: Vec<_> = bodies
+                .iter()
+                .map(|body| index.insert(body.bounds()))
+                .collect();
+            b.iter(|| black_box(simulate(&mut index, &mut bodies, &entries)));
+        });
+    }
+    group.finish();
+}
+
+fn broad_phase(c: &mut Criterion) {
+    bench_index(c, "quadtree", || {
+        QuadTree::<(), 4, 16, 8>::new(AxisAlignedBoundingBox::from_center(
+            Vec2::ZERO,
+            Vec2::ONE * AREA * 2.0,
+        ))
+    });
+    bench_index(c, "spatial_hash", || SpatialHash::<()>::new(8.0));
+}
+
+criterion_group!(benches, broad_phase);
+criterion_main!(benches);
@@ -66,7 +66,7 @@ impl AxisAlignedBoundingBox {
     }
 
     pub fn bottom_right(&self) -> Vec2 {
-        self.bottom_left + self.size + Vec2::X
+        self.bottom_left + self.size * Vec2::X
     }
 
     pub fn center(&self) -> Vec2 {
@@ -99,7 +99,7 @@ impl AxisAlignedBoundingBox {
         point[0] >= self.left()
             && point[0] < self.right()
             && point[1] < self.top()
-            && point[0] >= self.bottom()
+            && point[1] >= self.bottom()
     }
 
     pub fn intersects(&self, other: &AxisAlignedBoundingBox) -> bool {
@@ -1,5 +1,7 @@
 mod broad_phase;
 mod quadtree;
+mod spatial_hash;
 
 pub use broad_phase::*;
 pub use quadtree::*;
+pub use spatial_hash::*;
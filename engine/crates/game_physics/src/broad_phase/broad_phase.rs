@@ -1,5 +1,9 @@
 use crate::bodies::AxisAlignedBoundingBox;
-use game_lib::bevy::math::Vec2;
+use game_lib::bevy::{math::Vec2, prelude::*};
+
+/// Identifies an item stored in a broad phase.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Entry(pub(super) usize);
 
 pub trait BroadPhase<'a> {
     type Id;
@@ -1,12 +1,12 @@
-use crate::{bodies::AxisAlignedBoundingBox, broad_phase::BroadPhase};
+use crate::{
+    bodies::AxisAlignedBoundingBox,
+    broad_phase::{BroadPhase, Entry},
+};
 use game_lib::{
-    bevy::{math::Vec2, prelude::*, utils::HashMap},
+    bevy::{math::Vec2, utils::HashMap},
     tracing::{self, instrument},
 };
 
-#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug, Hash)]
-pub struct Entry(usize);
-
 #[derive(Clone, Debug)]
 pub enum QuadTreeNode<const MIN_ENTRIES: usize, const MAX_ENTRIES: usize, const MAX_DEPTH: usize> {
     Inner {
@@ -75,14 +75,14 @@ impl<const MIN_ENTRIES: usize, const MAX_ENTRIES: usize, const MAX_DEPTH: usize>
                         children: Box::new([
                             QuadTreeNode::Leaf {
                                 bounds: AxisAlignedBoundingBox::new(
-                                    leaf_bounds.top_left(),
+                                    leaf_bounds.bottom_left(),
                                     child_size,
                                 ),
                                 entries: HashMap::default(),
                             },
                             QuadTreeNode::Leaf {
                                 bounds: AxisAlignedBoundingBox::new(
-                                    Vec2::new(leaf_bounds.center()[0], leaf_bounds.top()),
+                                    Vec2::new(leaf_bounds.center()[0], leaf_bounds.bottom()),
                                     child_size,
                                 ),
                                 entries: HashMap::default(),
@@ -0,0 +1,210 @@
+use crate::{
+    bodies::AxisAlignedBoundingBox,
+    broad_phase::{BroadPhase, Entry},
+};
+use game_lib::{
+    bevy::{
+        math::Vec2,
+        utils::{HashMap, HashSet},
+    },
+    tracing::{self, instrument},
+};
+
+/// Range of grid cells covered by some bounds, stored as the inclusive
+/// bottom left and top right cell coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+struct CellRange {
+    min: (i32, i32),
+    max: (i32, i32),
+}
+
+impl CellRange {
+    fn iter(self) -> impl Iterator<Item = (i32, i32)> {
+        (self.min.0..=self.max.0).flat_map(move |x| (self.min.1..=self.max.1).map(move |y| (x, y)))
+    }
+}
+
+/// A uniform grid of cells, each storing the entries which overlap it. Unlike
+/// a [`QuadTree`](crate::broad_phase::QuadTree), the grid has no bounds, so it
+/// works well for an infinite world as long as items are not much larger than
+/// a cell.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<T> {
+    id: usize,
+    cell_size: f32,
+    entries: HashMap<Entry, (T, AxisAlignedBoundingBox)>,
+    cells: HashMap<(i32, i32), HashSet<Entry>>,
+}
+
+impl<T> SpatialHash<T> {
+    /// Creates an empty grid with square cells of the given size.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        SpatialHash {
+            id: 0,
+            cell_size,
+            entries: HashMap::default(),
+            cells: HashMap::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, point: Vec2) -> (i32, i32) {
+        let cell = point / self.cell_size;
+        (cell.x.floor() as i32, cell.y.floor() as i32)
+    }
+
+    fn cell_range(&self, bounds: AxisAlignedBoundingBox) -> CellRange {
+        CellRange {
+            min: self.cell(bounds.bottom_left()),
+            max: self.cell(bounds.top_right()),
+        }
+    }
+
+    #[instrument(skip(self, item, bounds))]
+    pub fn insert(&mut self, item: T, bounds: AxisAlignedBoundingBox) -> Entry {
+        // Get a new entry ID
+        let entry = Entry(self.id);
+        self.id += 1;
+
+        for cell in self.cell_range(bounds).iter() {
+            self.cells.entry(cell).or_default().insert(entry);
+        }
+
+        self.entries.insert(entry, (item, bounds));
+        entry
+    }
+
+    #[instrument(skip(self, entry))]
+    pub fn remove(&mut self, entry: Entry) -> Option<(T, AxisAlignedBoundingBox)> {
+        let (item, bounds) = self.entries.remove(&entry)?;
+        for cell in self.cell_range(bounds).iter() {
+            self.remove_from_cell(cell, entry);
+        }
+
+        Some((item, bounds))
+    }
+
+    fn remove_from_cell(&mut self, cell: (i32, i32), entry: Entry) {
+        if let Some(entries) = self.cells.get_mut(&cell) {
+            entries.remove(&entry);
+            if entries.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    #[instrument(skip(self, entry))]
+    pub fn get(&self, entry: Entry) -> Option<&T> {
+        self.entries.get(&entry).map(|(item, _)| item)
+    }
+
+    #[instrument(skip(self, entry))]
+    pub fn get_mut(&mut self, entry: Entry) -> Option<&mut T> {
+        self.entries.get_mut(&entry).map(|(item, _)| item)
+    }
+
+    #[instrument(skip(self, entry))]
+    pub fn get_bounds(&self, entry: Entry) -> Option<AxisAlignedBoundingBox> {
+        self.entries.get(&entry).map(|&(_, bounds)| bounds)
+    }
+
+    /// Moves an entry, only updating the cells it entered or left.
+    #[instrument(skip(self, entry, bounds))]
+    pub fn set_bounds(
+        &mut self,
+        entry: Entry,
+        bounds: AxisAlignedBoundingBox,
+    ) -> Option<AxisAlignedBoundingBox> {
+        let old_range = self.cell_range(self.get_bounds(entry)?);
+        let new_range = self.cell_range(bounds);
+        let old_bounds = std::mem::replace(&mut self.entries.get_mut(&entry)?.1, bounds);
+        if old_range == new_range {
+            return Some(old_bounds);
+        }
+
+        let contains = |range: CellRange, (x, y): (i32, i32)| {
+            x >= range.min.0 && x <= range.max.0 && y >= range.min.1 && y <= range.max.1
+        };
+        for cell in old_range.iter().filter(|&cell| !contains(new_range, cell)) {
+            self.remove_from_cell(cell, entry);
+        }
+        for cell in new_range.iter().filter(|&cell| !contains(old_range, cell)) {
+            self.cells.entry(cell).or_default().insert(entry);
+        }
+
+        Some(old_bounds)
+    }
+}
+
+impl<'a, T> BroadPhase<'a> for SpatialHash<T> {
+    type Id = Entry;
+    type QueryBounds = Vec<Entry>;
+    type QueryPoint = Vec<Entry>;
+
+    #[instrument(skip(self, bounds))]
+    fn query_bounds<'b: 'a>(&'b self, bounds: AxisAlignedBoundingBox) -> Self::QueryBounds {
+        // Entries spanning multiple cells are only returned once
+        let mut entries: Vec<_> = self
+            .cell_range(bounds)
+            .iter()
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        entries.sort_by_key(|entry| entry.0);
+        entries.dedup();
+        entries
+    }
+
+    #[instrument(skip(self, point))]
+    fn query_point<'b: 'a>(&'b self, point: Vec2) -> Self::QueryPoint {
+        let mut entries: Vec<_> = self
+            .cells
+            .get(&self.cell(point))
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        entries.sort_by_key(|entry| entry.0);
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(x: f32, y: f32, width: f32, height: f32) -> AxisAlignedBoundingBox {
+        AxisAlignedBoundingBox::new(Vec2::new(x, y), Vec2::new(width, height))
+    }
+
+    #[test]
+    fn query_finds_entries_in_overlapping_cells() {
+        let mut hash = SpatialHash::new(4.0);
+        let near = hash.insert("near", aabb(1.0, 1.0, 1.0, 1.0));
+        let spanning = hash.insert("spanning", aabb(-2.0, -2.0, 8.0, 3.0));
+        hash.insert("far", aabb(100.0, -100.0, 1.0, 1.0));
+
+        let found = hash.query_bounds(aabb(0.0, 0.0, 3.0, 3.0));
+        assert_eq!(vec![near, spanning], found);
+        assert_eq!(vec![near], hash.query_point(Vec2::new(2.0, 3.0)));
+    }
+
+    #[test]
+    fn moved_entries_leave_old_cells() {
+        let mut hash = SpatialHash::new(4.0);
+        let entry = hash.insert((), aabb(1.0, 1.0, 1.0, 1.0));
+        hash.set_bounds(entry, aabb(9.0, 1.0, 1.0, 1.0));
+
+        assert!(hash.query_point(Vec2::new(1.5, 1.5)).is_empty());
+        assert_eq!(vec![entry], hash.query_point(Vec2::new(9.5, 1.5)));
+        assert_eq!(Some(aabb(9.0, 1.0, 1.0, 1.0)), hash.get_bounds(entry));
+
+        hash.remove(entry);
+        assert!(hash.cells.is_empty());
+    }
+}
@@ -9,6 +9,7 @@ use game_tiles::{EntityWorldPosition, EntityWorldRect};
 #[derive(Bundle, Default)]
 pub struct PhysicsBundle {
     pub bounds: EntityWorldRect,
+    pub previous_bounds: PreviousBounds,
     pub body_type: BodyType,
     pub mass: Mass,
 
@@ -16,7 +17,13 @@ pub struct PhysicsBundle {
     pub acceleration: Acceleration,
     pub velocity: Velocity,
 
+    pub material: PhysicsMaterial,
+
     pub jump_status: JumpStatus,
+    pub drop_through: DropThroughPlatforms,
+    pub standing_on: StandingOn,
+    pub submerged: Submerged,
+    pub idle_steps: IdleSteps,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
@@ -24,6 +31,9 @@ pub struct PhysicsBundle {
 pub enum JumpStatus {
     OnGround,
     InAir { jumps: u32 },
+
+    /// The body is submerged in a fluid and can swim upwards.
+    Swimming,
 }
 
 impl Default for JumpStatus {
@@ -32,6 +42,153 @@ impl Default for JumpStatus {
     }
 }
 
+/// How a body accelerates when moving on its own, such as a player or NPC
+/// walking. Movement is applied as forces each physics step, in the direction
+/// given by the body's [`MoveDirection`].
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct MovementConfig {
+    /// Acceleration in `m/s^2` while standing on the ground.
+    pub ground_acceleration: f32,
+
+    /// Acceleration in `m/s^2` while in the air or swimming.
+    pub air_acceleration: f32,
+
+    /// Horizontal speed in `m/s` the body accelerates up to.
+    pub max_speed: f32,
+
+    /// Deceleration in `m/s^2` while standing on the ground without moving in
+    /// any direction. This is multiplied by the friction of the body's
+    /// material combined with the tile it's standing on, so slippery bodies
+    /// and surfaces take longer to stop.
+    pub deceleration: f32,
+
+    /// Acceleration is multiplied by this while moving against the body's
+    /// current velocity, making it quicker to turn around.
+    pub turn_boost: f32,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        MovementConfig {
+            ground_acceleration: 40.0,
+            air_acceleration: 15.0,
+            max_speed: 5.0,
+            deceleration: 30.0,
+            turn_boost: 2.0,
+        }
+    }
+}
+
+/// Horizontal direction the body is trying to move in, in the range
+/// `[-1, 1]`. This is scaled by [`MovementConfig::max_speed`] to get the
+/// speed the body accelerates towards.
+#[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct MoveDirection(pub f32);
+
+/// Whether the body should fall through one-way platforms it is standing on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct DropThroughPlatforms(pub bool);
+
+/// The moving body this body landed on during the most recent physics step,
+/// if any. Bodies standing on a moving body are carried along with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, From, Into)]
+pub struct StandingOn(pub Option<Entity>);
+
+/// The bounds of the body before the most recent physics step. This is used to
+/// interpolate the body's transform between steps, and is `None` until the
+/// body has been stepped for the first time.
+#[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct PreviousBounds(pub Option<EntityWorldRect>);
+
+/// Surface properties of a body or tile. When a body touches a tile, their
+/// materials are combined to determine how the body responds to the contact.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PhysicsMaterial {
+    /// Coulomb friction coefficient. Bodies sliding along a surface lose this
+    /// much tangential velocity for every unit of velocity the surface stops
+    /// them from moving into it.
+    pub friction: f32,
+
+    /// Fraction of velocity a body keeps when bouncing off of a surface.
+    pub restitution: f32,
+}
+
+impl PhysicsMaterial {
+    pub const fn new(friction: f32, restitution: f32) -> Self {
+        PhysicsMaterial {
+            friction,
+            restitution,
+        }
+    }
+
+    /// Combines the materials of two surfaces in contact with each other.
+    pub fn combine(self, other: PhysicsMaterial) -> PhysicsMaterial {
+        PhysicsMaterial {
+            friction: (self.friction * other.friction).sqrt(),
+            restitution: self.restitution.max(other.restitution),
+        }
+    }
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial::new(1.0, 0.0)
+    }
+}
+
+/// Properties of a fluid that bodies can be submerged in. Fluids come from
+/// liquid tiles, or from entities with bounds and this component which act as
+/// fluid zones.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Fluid {
+    /// Density in `kg/m^2`. Bodies which are less dense than the fluid float
+    /// in it.
+    pub density: f32,
+
+    /// Drag coefficient in `kg/m`, applied in proportion to how much of a body
+    /// is submerged.
+    pub drag: f32,
+}
+
+impl Fluid {
+    pub const WATER: Fluid = Fluid::new(15.0, 20.0);
+
+    pub const fn new(density: f32, drag: f32) -> Self {
+        Fluid { density, drag }
+    }
+}
+
+impl Default for Fluid {
+    fn default() -> Self {
+        Fluid::WATER
+    }
+}
+
+/// Fraction of the body's area that is submerged in fluids, in the range
+/// `[0, 1]`.
+#[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct Submerged(pub f32);
+
+/// Marks a body as sleeping. Sleeping bodies are skipped by the physics engine
+/// until they are woken up by a nearby tile changing, a force or velocity
+/// being applied to them, or another body touching them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Sleeping;
+
+/// Number of consecutive steps the body has been moving slower than
+/// [`PhysicsState::sleep_velocity`](crate::PhysicsState::sleep_velocity).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, From, Into, Reflect)]
+#[reflect(Component)]
+pub struct IdleSteps(pub u32);
+
 /// How the body should be treated by the physics engine.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
 #[reflect(Component)]
@@ -43,6 +200,11 @@ pub enum BodyType {
     /// Body cannot move, however other kinematic bodies can still collide with
     /// it.
     Static,
+
+    /// Body moves with its velocity, but is not affected by forces or tiles.
+    /// Kinematic bodies collide with it, are pushed out of its way, and are
+    /// carried along while standing on it.
+    Moving,
 }
 
 impl Default for BodyType {
@@ -51,6 +213,21 @@ impl Default for BodyType {
     }
 }
 
+/// Moves a [`BodyType::Moving`] body between waypoints at a constant speed,
+/// returning to the first waypoint after reaching the last one.
+#[derive(Clone, PartialEq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct PlatformPath {
+    /// Positions for the bottom left corner of the body to move between.
+    pub waypoints: Vec<EntityWorldPosition>,
+
+    /// Speed in `m/s`.
+    pub speed: f32,
+
+    /// Index of the waypoint the body is currently moving towards.
+    pub target: usize,
+}
+
 /// Velocity in `m/s`. This is **not** reset once it has been applied by the
 /// physics engine.
 #[derive(Clone, Copy, PartialEq, Debug, Default, From, Into, Reflect)]
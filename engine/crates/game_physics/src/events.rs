@@ -11,7 +11,7 @@ pub struct TileCollision {
     pub tile_position: TileWorldPosition,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Reflect)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Reflect)]
 pub enum TileCollisionAxis {
     X,
     Y,
@@ -6,13 +6,22 @@ mod components;
 mod events;
 mod line;
 mod plugin;
+mod query;
 mod resources;
+mod sweep;
 mod systems;
+mod tiles;
+
+#[cfg(test)]
+mod test_util;
 
 pub use components::*;
 pub use events::*;
 pub use line::*;
 pub use plugin::*;
+pub use query::*;
 pub use resources::*;
+pub use sweep::*;
+pub use tiles::*;
 
 game_lib::fix_bevy_derive!(game_lib::bevy);
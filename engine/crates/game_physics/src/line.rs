@@ -1,17 +1,43 @@
+use crate::bodies::AxisAlignedBoundingBox;
+use game_lib::bevy::math::Vec2;
+
+/// An infinite line.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Line {
+    /// The line `y = mx + b`.
     Standard { m: f32, b: f32 },
+
+    /// The line `x = x`.
     Vertical { x: f32 },
 }
 
 impl Line {
+    /// Gets the line passing through two points, or `None` if the points are
+    /// the same.
+    pub fn from_points(a: Vec2, b: Vec2) -> Option<Line> {
+        if a == b {
+            None
+        } else if a.x == b.x {
+            Some(Line::Vertical { x: a.x })
+        } else {
+            let m = (b.y - a.y) / (b.x - a.x);
+            Some(Line::Standard {
+                m,
+                b: a.y - m * a.x,
+            })
+        }
+    }
+
     pub fn intersection(self, other: Line) -> Intersection {
         match (self, other) {
             (l1, l2) if l1 == l2 => Intersection::Line(self),
             (Line::Standard { m: m1, b: b1 }, Line::Standard { m: m2, b: b2 }) if m1 != m2 => {
-                let x = (m1 - m2) / (b2 - b1);
+                // m1 * x + b1 = m2 * x + b2
+                let x = (b2 - b1) / (m1 - m2);
                 Intersection::Point(x, m1 * x + b1)
             }
+            (Line::Standard { m, b }, Line::Vertical { x })
+            | (Line::Vertical { x }, Line::Standard { m, b }) => Intersection::Point(x, m * x + b),
             _ => Intersection::None,
         }
     }
@@ -21,7 +47,7 @@ impl Line {
     }
 
     pub fn get_x(self, y: f32) -> Option<f32> {
-        self.into_fn().map(|f| f(y))
+        self.into_inverse_fn().map(|f| f(y))
     }
 
     pub fn into_fn(self) -> Option<impl Fn(f32) -> f32> {
@@ -51,3 +77,332 @@ pub enum Intersection {
     Point(f32, f32),
     Line(Line),
 }
+
+/// Where a segment or ray first touched something.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LineHit {
+    /// Parameter of the hit along the segment or ray. For segments this is in
+    /// the range `[0, 1]`, and for rays it is the distance along the ray in
+    /// units of its direction.
+    pub t: f32,
+
+    pub point: Vec2,
+
+    /// Normal of the surface that was hit, facing against the direction of
+    /// the segment or ray. If it started inside what it hit, this points
+    /// directly against its direction.
+    pub normal: Vec2,
+}
+
+/// A line segment from `start` to `end`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Segment {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Segment {
+    pub fn new(start: Vec2, end: Vec2) -> Self {
+        Segment { start, end }
+    }
+
+    /// Gets the offset from the start of the segment to the end of it.
+    pub fn delta(self) -> Vec2 {
+        self.end - self.start
+    }
+
+    pub fn length(self) -> f32 {
+        self.delta().length()
+    }
+
+    /// Gets the point at parameter `t`, where `0` is the start and `1` is the
+    /// end of the segment.
+    pub fn point_at(self, t: f32) -> Vec2 {
+        self.start + self.delta() * t
+    }
+
+    /// Gets the line this segment lies on, or `None` if the segment is a
+    /// single point.
+    pub fn line(self) -> Option<Line> {
+        Line::from_points(self.start, self.end)
+    }
+
+    /// Finds the first point along this segment where it touches another one.
+    /// If the segments overlap, this is the first point of the overlap.
+    pub fn intersect_segment(self, other: Segment) -> Option<LineHit> {
+        cast_segment(self.start, self.delta(), 1.0, other)
+    }
+
+    /// Finds the first point along this segment inside the given bounds.
+    pub fn intersect_aabb(self, aabb: AxisAlignedBoundingBox) -> Option<LineHit> {
+        cast_aabb(self.start, self.delta(), 1.0, aabb)
+    }
+}
+
+/// A half-line starting at `origin` and extending in `direction`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Ray {
+    pub origin: Vec2,
+    pub direction: Vec2,
+}
+
+impl Ray {
+    pub fn new(origin: Vec2, direction: Vec2) -> Self {
+        Ray { origin, direction }
+    }
+
+    pub fn point_at(self, t: f32) -> Vec2 {
+        self.origin + self.direction * t
+    }
+
+    /// Finds the first point along this ray where it touches a segment.
+    pub fn intersect_segment(self, segment: Segment) -> Option<LineHit> {
+        cast_segment(self.origin, self.direction, f32::INFINITY, segment)
+    }
+
+    /// Finds the first point along this ray inside the given bounds.
+    pub fn intersect_aabb(self, aabb: AxisAlignedBoundingBox) -> Option<LineHit> {
+        cast_aabb(self.origin, self.direction, f32::INFINITY, aabb)
+    }
+}
+
+/// Gets the z-component of the cross product of two vectors.
+fn cross(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/// Casts `origin + delta * t` for `t` in `[0, max_t]` against a segment.
+fn cast_segment(origin: Vec2, delta: Vec2, max_t: f32, segment: Segment) -> Option<LineHit> {
+    let delta_squared = delta.dot(delta);
+    if delta_squared == 0.0 {
+        return None;
+    }
+
+    let other_delta = segment.delta();
+    let offset = segment.start - origin;
+    let denominator = cross(delta, other_delta);
+    if denominator.abs() <= f32::EPSILON * delta_squared.max(other_delta.dot(other_delta)) {
+        // Parallel lines can only touch if they're collinear
+        if cross(offset, delta).abs() > f32::EPSILON * delta_squared.sqrt() {
+            return None;
+        }
+
+        // Find where the other segment overlaps this one
+        let t0 = offset.dot(delta) / delta_squared;
+        let t1 = t0 + other_delta.dot(delta) / delta_squared;
+        let enter = t0.min(t1).max(0.0);
+        let exit = t0.max(t1).min(max_t);
+        return (enter <= exit).then(|| LineHit {
+            t: enter,
+            point: origin + delta * enter,
+            normal: -delta / delta_squared.sqrt(),
+        });
+    }
+
+    let t = cross(offset, other_delta) / denominator;
+    let u = cross(offset, delta) / denominator;
+    if !(0.0..=max_t).contains(&t) || !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    // Face the normal against the direction of the cast
+    let normal = Vec2::new(-other_delta.y, other_delta.x) / other_delta.length();
+    let normal = if normal.dot(delta) > 0.0 {
+        -normal
+    } else {
+        normal
+    };
+
+    Some(LineHit {
+        t,
+        point: origin + delta * t,
+        normal,
+    })
+}
+
+/// Casts `origin + delta * t` for `t` in `[0, max_t]` against an AABB using
+/// the slab method.
+fn cast_aabb(
+    origin: Vec2,
+    delta: Vec2,
+    max_t: f32,
+    aabb: AxisAlignedBoundingBox,
+) -> Option<LineHit> {
+    let slabs = [
+        (origin.x, delta.x, aabb.left(), aabb.right(), Vec2::X),
+        (origin.y, delta.y, aabb.bottom(), aabb.top(), Vec2::Y),
+    ];
+
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+    for &(start, speed, min, max, axis) in slabs.iter() {
+        if speed == 0.0 {
+            // Parallel to the slab, so it must already be inside it
+            if start < min || start > max {
+                return None;
+            }
+
+            continue;
+        }
+
+        let a = (min - start) / speed;
+        let b = (max - start) / speed;
+        let (axis_enter, axis_exit) = (a.min(b), a.max(b));
+        if axis_enter > enter {
+            enter = axis_enter;
+            normal = -axis * speed.signum();
+        }
+
+        exit = exit.min(axis_exit);
+    }
+
+    if enter > exit || exit < 0.0 || enter > max_t {
+        None
+    } else if enter < 0.0 {
+        // Started inside the bounds
+        let length = delta.length();
+        let normal = if length > 0.0 {
+            -delta / length
+        } else {
+            Vec2::ZERO
+        };
+        Some(LineHit {
+            t: 0.0,
+            point: origin,
+            normal,
+        })
+    } else {
+        Some(LineHit {
+            t: enter,
+            point: origin + delta * enter,
+            normal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: Vec2, actual: Vec2) {
+        assert!(
+            (expected - actual).length() < 0.0001,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn standard_lines_intersect() {
+        let l1 = Line::Standard { m: 1.0, b: 0.0 };
+        let l2 = Line::Standard { m: -1.0, b: 2.0 };
+
+        assert_eq!(Intersection::Point(1.0, 1.0), l1.intersection(l2));
+        assert_eq!(Intersection::Point(1.0, 1.0), l2.intersection(l1));
+    }
+
+    #[test]
+    fn vertical_lines_intersect() {
+        let standard = Line::Standard { m: 2.0, b: 1.0 };
+        let vertical = Line::Vertical { x: 3.0 };
+
+        assert_eq!(
+            Intersection::Point(3.0, 7.0),
+            standard.intersection(vertical)
+        );
+        assert_eq!(
+            Intersection::Point(3.0, 7.0),
+            vertical.intersection(standard)
+        );
+        assert_eq!(
+            Intersection::None,
+            vertical.intersection(Line::Vertical { x: 4.0 })
+        );
+        assert_eq!(
+            Intersection::Line(vertical),
+            vertical.intersection(vertical)
+        );
+    }
+
+    #[test]
+    fn parallel_lines_do_not_intersect() {
+        let l1 = Line::Standard { m: 0.5, b: 0.0 };
+        let l2 = Line::Standard { m: 0.5, b: 1.0 };
+
+        assert_eq!(Intersection::None, l1.intersection(l2));
+    }
+
+    #[test]
+    fn get_x_inverts_get_y() {
+        let line = Line::from_points(Vec2::new(0.0, 1.0), Vec2::new(2.0, 5.0)).unwrap();
+
+        assert_eq!(Some(5.0), line.get_y(2.0));
+        assert_eq!(Some(2.0), line.get_x(5.0));
+        assert_eq!(None, Line::Standard { m: 0.0, b: 1.0 }.get_x(1.0));
+        assert_eq!(Some(3.0), Line::Vertical { x: 3.0 }.get_x(10.0));
+    }
+
+    #[test]
+    fn crossing_segments_intersect() {
+        let a = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0));
+        let b = Segment::new(Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0));
+        let hit = a.intersect_segment(b).expect("segments should cross");
+
+        assert!((hit.t - 0.25).abs() < 0.0001);
+        assert_close(Vec2::new(1.0, 0.0), hit.point);
+        assert_close(-Vec2::X, hit.normal);
+    }
+
+    #[test]
+    fn separate_segments_do_not_intersect() {
+        let a = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0));
+        let b = Segment::new(Vec2::new(2.0, -1.0), Vec2::new(2.0, 1.0));
+        let c = Segment::new(Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0));
+
+        assert_eq!(None, a.intersect_segment(b));
+        assert_eq!(None, a.intersect_segment(c));
+    }
+
+    #[test]
+    fn collinear_segments_intersect_at_overlap() {
+        let a = Segment::new(Vec2::new(0.0, 0.0), Vec2::new(4.0, 0.0));
+        let b = Segment::new(Vec2::new(3.0, 0.0), Vec2::new(6.0, 0.0));
+        let hit = a.intersect_segment(b).expect("segments should overlap");
+
+        assert!((hit.t - 0.75).abs() < 0.0001);
+        assert_close(Vec2::new(3.0, 0.0), hit.point);
+    }
+
+    #[test]
+    fn segment_enters_aabb() {
+        let aabb = AxisAlignedBoundingBox::new(Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0));
+        let segment = Segment::new(Vec2::new(0.0, 1.0), Vec2::new(4.0, 1.0));
+        let hit = segment.intersect_aabb(aabb).expect("segment should enter");
+
+        assert!((hit.t - 0.5).abs() < 0.0001);
+        assert_close(Vec2::new(2.0, 1.0), hit.point);
+        assert_close(-Vec2::X, hit.normal);
+
+        let short = Segment::new(Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0));
+        assert_eq!(None, short.intersect_aabb(aabb));
+
+        let inside = Segment::new(Vec2::new(3.0, 1.0), Vec2::new(3.0, 5.0));
+        let hit = inside.intersect_aabb(aabb).expect("segment starts inside");
+        assert_eq!(0.0, hit.t);
+        assert_close(-Vec2::Y, hit.normal);
+    }
+
+    #[test]
+    fn ray_ignores_what_is_behind_it() {
+        let segment = Segment::new(Vec2::new(-1.0, -1.0), Vec2::new(-1.0, 1.0));
+        let aabb = AxisAlignedBoundingBox::new(Vec2::new(5.0, -1.0), Vec2::new(1.0, 2.0));
+        let ray = Ray::new(Vec2::ZERO, Vec2::X);
+
+        assert_eq!(None, ray.intersect_segment(segment));
+        let hit = ray.intersect_aabb(aabb).expect("ray should hit the box");
+        assert!((hit.t - 5.0).abs() < 0.0001);
+        assert_close(Vec2::new(5.0, 0.0), ray.point_at(hit.t));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
-    bodies::AxisAlignedBoundingBox, systems, Acceleration, BodyType, Drag, EntityCollision, Forces,
-    Gravity, Mass, PhysicsState, TileCollision, Velocity, TileCollisionAxis
+    bodies::AxisAlignedBoundingBox, systems, Acceleration, BodyType, BroadPhaseConfig, Drag,
+    DropThroughPlatforms, EntityCollision, Fluid, Forces, Gravity, IdleSteps, Mass, MoveDirection,
+    MovementConfig, PhysicsMaterial, PhysicsState, PlatformPath, PreviousBounds, Sleeping,
+    SlopeDirection, Submerged, TileCollision, TileCollisionAxis, TileCollisionShape, Velocity,
 };
 use game_core::{combinators::if_all, modes::ModeExt, GameStage, GlobalMode, ModeEvent};
 use game_lib::bevy::{ecs as bevy_ecs, prelude::*};
@@ -18,8 +20,21 @@ impl Plugin for PhysicsPlugin {
             .register_type::<Gravity>()
             .register_type::<Drag>()
             .register_type::<BodyType>()
+            .register_type::<PlatformPath>()
+            .register_type::<MovementConfig>()
+            .register_type::<MoveDirection>()
+            .register_type::<Fluid>()
+            .register_type::<Submerged>()
+            .register_type::<PreviousBounds>()
+            .register_type::<PhysicsMaterial>()
+            .register_type::<Sleeping>()
+            .register_type::<IdleSteps>()
+            .register_type::<DropThroughPlatforms>()
+            .register_type::<TileCollisionShape>()
+            .register_type::<SlopeDirection>()
             .register_type::<AxisAlignedBoundingBox>()
             .register_type::<PhysicsState>()
+            .register_type::<BroadPhaseConfig>()
             .register_type::<EntityCollision>()
             .register_type::<TileCollisionAxis>()
             .register_type::<TileCollision>()
@@ -44,8 +59,10 @@ impl Plugin for PhysicsPlugin {
                 SystemSet::new()
                     .label(PhysicsPlugin)
                     .label(PhysicsSystem::UpdateState)
+                    .after(TileSystem::DetectChanges)
                     .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
-                    .with_system(systems::update_physics_state.system()),
+                    .with_system(systems::update_physics_state.system())
+                    .with_system(systems::wake_bodies.system()),
             )
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
@@ -55,11 +72,13 @@ impl Plugin for PhysicsPlugin {
                     .after(PhysicsSystem::UpdateState)
                     .with_run_criteria(if_all(vec![
                         GlobalMode::InGame.on(ModeEvent::Active),
-                        Box::new(systems::if_physics_lagged.system()),
+                        Box::new(systems::while_physics_lagged.system()),
                     ]))
                     .with_system(
                         systems::add_kinematic_forces
                             .system()
+                            .chain(systems::add_movement_forces.system())
+                            .chain(systems::add_fluid_forces.system())
                             .chain(systems::apply_forces.system())
                             .chain(systems::apply_acceleration.system()),
                     ),
@@ -68,12 +87,24 @@ impl Plugin for PhysicsPlugin {
                 GameStage::GameUpdate,
                 SystemSet::new()
                     .label(PhysicsPlugin)
-                    .label(PhysicsSystem::Run)
+                    .label(PhysicsSystem::MovePlatforms)
                     .after(PhysicsSystem::Prepare)
                     .with_run_criteria(if_all(vec![
                         GlobalMode::InGame.on(ModeEvent::Active),
                         Box::new(systems::while_physics_lagged.system()),
                     ]))
+                    .with_system(systems::move_platforms.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(PhysicsPlugin)
+                    .label(PhysicsSystem::Run)
+                    .after(PhysicsSystem::MovePlatforms)
+                    .with_run_criteria(if_all(vec![
+                        GlobalMode::InGame.on(ModeEvent::Active),
+                        Box::new(systems::while_physics_lagged.system()),
+                    ]))
                     .with_system(systems::step.system()),
             )
             .add_system_set_to_stage(
@@ -81,23 +112,39 @@ impl Plugin for PhysicsPlugin {
                 SystemSet::new()
                     .label(PhysicsPlugin)
                     .label(PhysicsSystem::Cleanup)
-                    .before(TileSystem::DetectRedraw)
                     .after(PhysicsSystem::Run)
-                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_run_criteria(if_all(vec![
+                        GlobalMode::InGame.on(ModeEvent::Active),
+                        Box::new(systems::while_physics_lagged.system()),
+                    ]))
+                    .with_system(systems::cleanup_kinematics.system())
+                    .with_system(systems::update_jump_status.system())
+                    .with_system(systems::update_sleeping.system()),
+            )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(PhysicsPlugin)
+                    .label(PhysicsSystem::Interpolate)
+                    .before(TileSystem::DetectRedraw)
+                    .after(PhysicsSystem::Cleanup)
+                    .with_run_criteria(if_all(vec![
+                        GlobalMode::InGame.on(ModeEvent::Active),
+                        Box::new(systems::if_physics_caught_up.system()),
+                    ]))
                     .with_system(systems::update_transforms.system()),
             )
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
                 SystemSet::new()
                     .label(PhysicsPlugin)
-                    .label(PhysicsSystem::Cleanup)
-                    .after(PhysicsSystem::Run)
+                    .label(PhysicsSystem::UpdateBodyTree)
+                    .after(PhysicsSystem::Cleanup)
                     .with_run_criteria(if_all(vec![
                         GlobalMode::InGame.on(ModeEvent::Active),
-                        Box::new(systems::if_physics_lagged.system()),
+                        Box::new(systems::if_physics_caught_up.system()),
                     ]))
-                    .with_system(systems::cleanup_kinematics.system())
-                    .with_system(systems::reset_jumps.system()),
+                    .with_system(systems::update_body_tree.system()),
             );
     }
 }
@@ -106,6 +153,9 @@ impl Plugin for PhysicsPlugin {
 pub enum PhysicsSystem {
     UpdateState,
     Prepare,
+    MovePlatforms,
     Run,
     Cleanup,
+    Interpolate,
+    UpdateBodyTree,
 }
@@ -0,0 +1,506 @@
+use crate::{BodyTree, TileCollisionShape, TilePhysicsExt, CONTACT_EPSILON};
+use game_lib::bevy::{
+    ecs::{self as bevy_ecs, system::SystemParam},
+    prelude::*,
+    utils::HashSet,
+};
+use game_tiles::{
+    EntityWorldPosition, EntityWorldRect, GameWorld, Tile, TileWorldPosition, TileWorldRect,
+};
+
+/// What a cast through the world hit.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PhysicsHitTarget {
+    Body(Entity),
+    Tile(Tile, TileWorldPosition),
+}
+
+/// The nearest hit of a cast through the world.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PhysicsHit {
+    pub target: PhysicsHitTarget,
+
+    /// Distance travelled along the cast before the hit.
+    pub distance: f32,
+
+    /// Normal of the surface that was hit. If the cast started overlapping
+    /// what it hit, this points against the direction of the cast.
+    pub normal: EntityWorldPosition,
+}
+
+/// Casts rays and shapes against both the tiles in the world and the bodies
+/// registered with the physics engine.
+#[derive(SystemParam)]
+pub struct PhysicsQuery<'a> {
+    world: Res<'a, GameWorld>,
+    body_tree: Res<'a, BodyTree>,
+    bodies: Query<'a, &'static EntityWorldRect>,
+}
+
+impl<'a> PhysicsQuery<'a> {
+    /// Casts a ray from `origin` in `direction`, returning the nearest hit
+    /// within `max_distance`. Bodies in `ignore` are never hit.
+    pub fn raycast(
+        &self,
+        origin: EntityWorldPosition,
+        direction: EntityWorldPosition,
+        max_distance: f32,
+        ignore: &[Entity],
+    ) -> Option<PhysicsHit> {
+        let bounds = EntityWorldRect::new(origin, EntityWorldPosition::ZERO);
+        self.aabb_cast(bounds, direction, max_distance, ignore)
+    }
+
+    /// Moves a box from `bounds` in `direction`, returning the nearest hit
+    /// within `max_distance`. Bodies in `ignore` are never hit. Casts with a
+    /// non-finite `max_distance` never hit anything.
+    pub fn aabb_cast(
+        &self,
+        bounds: EntityWorldRect,
+        direction: EntityWorldPosition,
+        max_distance: f32,
+        ignore: &[Entity],
+    ) -> Option<PhysicsHit> {
+        let direction = normalize(direction.into())?;
+        if !max_distance.is_finite() {
+            return None;
+        }
+
+        let swept = swept_bounds(bounds, direction * max_distance);
+        let tile_hit = self.cast_tiles(bounds, direction, max_distance);
+        let body_hits = self
+            .body_tree
+            .query_bounds(swept)
+            .filter(|entity| !ignore.contains(entity))
+            .filter_map(|entity| {
+                let other = *self.bodies.get(entity).ok()?;
+                let (distance, normal) =
+                    cast_polygon(bounds, direction, max_distance, &rect_polygon(other))?;
+                Some(PhysicsHit {
+                    target: PhysicsHitTarget::Body(entity),
+                    distance,
+                    normal: normal.into(),
+                })
+            });
+
+        tile_hit
+            .into_iter()
+            .chain(body_hits)
+            .fold(None, |nearest, hit| match nearest {
+                Some(nearest) if nearest.distance <= hit.distance => Some(nearest),
+                _ => Some(hit),
+            })
+    }
+
+    /// Finds the nearest tile hit by a moving box. Tiles are visited in the
+    /// order the box reaches them, so only the tiles along the cast are
+    /// checked, and the search stops once no tile left can be hit sooner than
+    /// the nearest hit found so far.
+    fn cast_tiles(
+        &self,
+        bounds: EntityWorldRect,
+        direction: Vec2,
+        max_distance: f32,
+    ) -> Option<PhysicsHit> {
+        let mut visited = HashSet::default();
+        let mut nearest: Option<PhysicsHit> = None;
+        let mut start = 0.0;
+        for end in GridSteps::new(bounds, direction, max_distance) {
+            if nearest.map_or(false, |nearest| nearest.distance < start) {
+                break;
+            }
+
+            // The box only enters new tiles when one of its leading edges
+            // crosses a tile boundary, so check the tiles it covers until then
+            let offset = EntityWorldPosition::from(direction * start);
+            let segment = EntityWorldRect::new(bounds.bottom_left + offset, bounds.size());
+            let covered = TileWorldRect::from(swept_bounds(segment, direction * (end - start)));
+            for position in covered.iter_positions() {
+                if !visited.insert(position) {
+                    continue;
+                }
+
+                let hit = self.cast_tile(bounds, direction, max_distance, position);
+                if let Some(hit) = hit {
+                    if nearest.map_or(true, |nearest| hit.distance < nearest.distance) {
+                        nearest = Some(hit);
+                    }
+                }
+            }
+
+            start = end;
+        }
+
+        nearest
+    }
+
+    fn cast_tile(
+        &self,
+        bounds: EntityWorldRect,
+        direction: Vec2,
+        max_distance: f32,
+        position: TileWorldPosition,
+    ) -> Option<PhysicsHit> {
+        let tile = (*self.world.get_tile(position).ok()?)?;
+        let shape = tile.collision_shape();
+        let polygon = tile_polygon(shape, position)?;
+        let (distance, normal) = cast_polygon(bounds, direction, max_distance, &polygon)?;
+
+        // One-way platforms can only be hit from above
+        if shape == TileCollisionShape::OneWay {
+            let top = position.y as f32 + 1.0;
+            if normal != Vec2::Y || bounds.bottom() < top - CONTACT_EPSILON {
+                return None;
+            }
+        }
+
+        Some(PhysicsHit {
+            target: PhysicsHitTarget::Tile(tile, position),
+            distance,
+            normal: normal.into(),
+        })
+    }
+}
+
+/// Distances along a cast at which the leading edges of a moving box cross
+/// into a new row or column of tiles, ending with the length of the cast.
+struct GridSteps {
+    /// Distance to the next boundary along each axis.
+    next: Vec2,
+
+    /// Distance between boundaries along each axis.
+    delta: Vec2,
+
+    max_distance: f32,
+
+    /// Number of boundaries that can still be crossed. This guards against
+    /// boundaries which precision makes impossible to step past.
+    remaining: u32,
+
+    done: bool,
+}
+
+impl GridSteps {
+    fn new(bounds: EntityWorldRect, direction: Vec2, max_distance: f32) -> Self {
+        let first = |leading_min: f32, leading_max: f32, speed: f32| {
+            if speed > 0.0 {
+                (leading_max.floor() + 1.0 - leading_max) / speed
+            } else if speed < 0.0 {
+                (leading_min.ceil() - 1.0 - leading_min) / speed
+            } else {
+                f32::INFINITY
+            }
+        };
+        let crossings = |speed: f32| (speed.abs() * max_distance.max(0.0)).ceil() as u32 + 1;
+
+        GridSteps {
+            next: Vec2::new(
+                first(bounds.left(), bounds.right(), direction.x),
+                first(bounds.bottom(), bounds.top(), direction.y),
+            ),
+            delta: Vec2::new(1.0 / direction.x.abs(), 1.0 / direction.y.abs()),
+            max_distance,
+            remaining: crossings(direction.x).saturating_add(crossings(direction.y)),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for GridSteps {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let distance = self.next.x.min(self.next.y);
+        if distance >= self.max_distance || self.remaining == 0 {
+            self.done = true;
+            return Some(self.max_distance);
+        }
+
+        self.remaining -= 1;
+        if self.next.x <= self.next.y {
+            self.next.x += self.delta.x;
+        } else {
+            self.next.y += self.delta.y;
+        }
+
+        Some(distance)
+    }
+}
+
+/// Gets the smallest rectangle containing the bounds before and after being
+/// moved by the given offset.
+fn swept_bounds(bounds: EntityWorldRect, offset: Vec2) -> EntityWorldRect {
+    let bottom_left = EntityWorldPosition::new(
+        bounds.left().min(bounds.left() + offset.x),
+        bounds.bottom().min(bounds.bottom() + offset.y),
+    );
+    let size = bounds.size() + EntityWorldPosition::new(offset.x.abs(), offset.y.abs());
+    EntityWorldRect::new(bottom_left, size)
+}
+
+fn rect_polygon(rect: EntityWorldRect) -> [Vec2; 4] {
+    [
+        rect.bottom_left().into(),
+        rect.bottom_right().into(),
+        rect.top_right().into(),
+        rect.top_left().into(),
+    ]
+}
+
+/// Gets the outline of a tile's collision shape. Every shape is bounded by
+/// the bottom and sides of its cell and a straight top surface, so they are
+/// all convex.
+fn tile_polygon(shape: TileCollisionShape, position: TileWorldPosition) -> Option<[Vec2; 4]> {
+    let origin = Vec2::new(position.x as f32, position.y as f32);
+    let left = shape.surface_height(0.0, 0.0)?;
+    let right = shape.surface_height(1.0, 1.0)?;
+    Some([
+        origin,
+        origin + Vec2::X,
+        origin + Vec2::new(1.0, right),
+        origin + Vec2::new(0.0, left),
+    ])
+}
+
+/// Moves a box along a normalized direction and finds where it first touches
+/// a convex polygon using the separating axis theorem. Returns the distance
+/// travelled and the normal of the surface hit.
+fn cast_polygon(
+    bounds: EntityWorldRect,
+    direction: Vec2,
+    max_distance: f32,
+    polygon: &[Vec2],
+) -> Option<(f32, Vec2)> {
+    let corners = rect_polygon(bounds);
+    let edge_normals = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .filter_map(|(&a, &b)| normalize(Vec2::new(a.y - b.y, b.x - a.x)));
+    let axes = vec![Vec2::X, Vec2::Y].into_iter().chain(edge_normals);
+
+    let project = |points: &[Vec2], axis: Vec2| {
+        points
+            .iter()
+            .map(|point| point.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), cur| {
+                (min.min(cur), max.max(cur))
+            })
+    };
+
+    let mut enter = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = -direction;
+    for axis in axes {
+        let (box_min, box_max) = project(&corners, axis);
+        let (polygon_min, polygon_max) = project(polygon, axis);
+        let speed = direction.dot(axis);
+
+        // Stationary along this axis, so the projections must already overlap
+        if speed.abs() <= f32::EPSILON {
+            if box_max <= polygon_min || box_min >= polygon_max {
+                return None;
+            }
+
+            continue;
+        }
+
+        // Get the interval where the projections overlap
+        let a = (polygon_min - box_max) / speed;
+        let b = (polygon_max - box_min) / speed;
+        let (axis_enter, axis_exit) = (a.min(b), a.max(b));
+        if axis_enter > enter {
+            enter = axis_enter;
+            normal = if speed > 0.0 { -axis } else { axis };
+        }
+
+        exit = exit.min(axis_exit);
+        if enter > exit {
+            return None;
+        }
+    }
+
+    if exit < 0.0 || enter > max_distance {
+        None
+    } else if enter < 0.0 {
+        // Already overlapping
+        Some((0.0, -direction))
+    } else {
+        Some((enter, normal))
+    }
+}
+
+fn normalize(vector: Vec2) -> Option<Vec2> {
+    let length = vector.length();
+    (length > f32::EPSILON).then(|| vector / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_close, flat_world, rect};
+    use game_tiles::TileWorldCoordinate;
+
+    /// A cast to run against the world.
+    struct Cast(Box<dyn Fn(&PhysicsQuery) -> Option<PhysicsHit> + Send + Sync>);
+
+    /// The result of the last cast.
+    struct CastResult(Option<PhysicsHit>);
+
+    fn run_cast(query: PhysicsQuery, cast: Res<Cast>, mut result: ResMut<CastResult>) {
+        result.0 = (cast.0)(&query);
+    }
+
+    /// Creates a world with a floor of stone along `y = 0` and the given tiles
+    /// placed on top of it, ready for casting against.
+    fn cast_world(tiles: &[(Tile, TileWorldCoordinate, TileWorldCoordinate)]) -> World {
+        let mut world = World::default();
+        world.insert_resource(flat_world(tiles));
+        world.insert_resource(BodyTree::default());
+        world
+    }
+
+    fn spawn_body(world: &mut World, bounds: EntityWorldRect) -> Entity {
+        let entity = world.spawn().insert(bounds).id();
+        world
+            .get_resource_mut::<BodyTree>()
+            .unwrap()
+            .update(entity, bounds);
+        entity
+    }
+
+    fn cast(
+        world: &mut World,
+        cast: impl Fn(&PhysicsQuery) -> Option<PhysicsHit> + Send + Sync + 'static,
+    ) -> Option<PhysicsHit> {
+        world.insert_resource(Cast(Box::new(cast)));
+        world.insert_resource(CastResult(None));
+
+        let mut stage = SystemStage::single_threaded();
+        stage.add_system(run_cast.system());
+        stage.run(world);
+        world.get_resource::<CastResult>().unwrap().0
+    }
+
+    #[test]
+    fn ray_hits_full_tile() {
+        let mut world = cast_world(&[]);
+        let hit = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(0.5, 5.0);
+            query.raycast(origin, -EntityWorldPosition::Y, 10.0, &[])
+        })
+        .expect("ray should hit the floor");
+
+        assert_eq!(
+            PhysicsHitTarget::Tile(Tile::Stone, TileWorldPosition::new(0, 0)),
+            hit.target
+        );
+        assert_close(4.0, hit.distance);
+        assert_eq!(EntityWorldPosition::Y, hit.normal);
+    }
+
+    #[test]
+    fn ray_misses_beyond_max_distance() {
+        let mut world = cast_world(&[]);
+        let hit = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(0.5, 5.0);
+            query.raycast(origin, -EntityWorldPosition::Y, 3.5, &[])
+        });
+
+        assert_eq!(None, hit);
+    }
+
+    #[test]
+    fn long_diagonal_ray_hits_nearest_tile() {
+        let mut world = cast_world(&[(Tile::Stone, 40, 41), (Tile::Stone, 20, 21)]);
+        let hit = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(0.25, 1.5);
+            let direction = EntityWorldPosition::new(1.0, 1.0);
+            query.raycast(origin, direction, 1000.0, &[])
+        })
+        .expect("ray should hit the nearer tile");
+
+        assert_eq!(
+            PhysicsHitTarget::Tile(Tile::Stone, TileWorldPosition::new(20, 21)),
+            hit.target
+        );
+        assert_close(19.75 * std::f32::consts::SQRT_2, hit.distance);
+        assert_eq!(-EntityWorldPosition::X, hit.normal);
+    }
+
+    #[test]
+    fn ray_hits_slope_with_its_normal() {
+        let mut world = cast_world(&[(Tile::StoneSlopeRight, 3, 1)]);
+        let hit = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(3.5, 5.0);
+            query.raycast(origin, -EntityWorldPosition::Y, 10.0, &[])
+        })
+        .expect("ray should hit the slope");
+
+        assert_eq!(
+            PhysicsHitTarget::Tile(Tile::StoneSlopeRight, TileWorldPosition::new(3, 1)),
+            hit.target
+        );
+        assert_close(3.5, hit.distance);
+        assert_close(-std::f32::consts::FRAC_1_SQRT_2, hit.normal.x);
+        assert_close(std::f32::consts::FRAC_1_SQRT_2, hit.normal.y);
+    }
+
+    #[test]
+    fn one_way_platform_is_only_hit_from_above() {
+        let mut world = cast_world(&[(Tile::WoodPlatform, 6, 3)]);
+        let from_above = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(6.5, 6.0);
+            query.raycast(origin, -EntityWorldPosition::Y, 10.0, &[])
+        })
+        .expect("ray should land on the platform");
+        let from_below = cast(&mut world, |query| {
+            let origin = EntityWorldPosition::new(6.5, 1.5);
+            query.raycast(origin, EntityWorldPosition::Y, 10.0, &[])
+        });
+
+        assert_eq!(
+            PhysicsHitTarget::Tile(Tile::WoodPlatform, TileWorldPosition::new(6, 3)),
+            from_above.target
+        );
+        assert_close(2.0, from_above.distance);
+        assert_eq!(EntityWorldPosition::Y, from_above.normal);
+        assert_eq!(None, from_below);
+    }
+
+    #[test]
+    fn aabb_cast_hits_body() {
+        let mut world = cast_world(&[]);
+        let body = spawn_body(&mut world, rect(10.0, 1.0, 1.0, 2.0));
+        let hit = cast(&mut world, |query| {
+            let bounds = rect(7.0, 1.0, 0.5, 0.5);
+            query.aabb_cast(bounds, EntityWorldPosition::X, 10.0, &[])
+        })
+        .expect("box should hit the body");
+
+        assert_eq!(PhysicsHitTarget::Body(body), hit.target);
+        assert_close(2.5, hit.distance);
+        assert_eq!(-EntityWorldPosition::X, hit.normal);
+    }
+
+    #[test]
+    fn ignored_bodies_are_not_hit() {
+        let mut world = cast_world(&[(Tile::Stone, 14, 1)]);
+        let body = spawn_body(&mut world, rect(10.0, 1.0, 1.0, 2.0));
+        let hit = cast(&mut world, move |query| {
+            let origin = EntityWorldPosition::new(8.0, 1.5);
+            query.raycast(origin, EntityWorldPosition::X, 10.0, &[body])
+        })
+        .expect("ray should pass the body and hit the wall");
+
+        assert_eq!(
+            PhysicsHitTarget::Tile(Tile::Stone, TileWorldPosition::new(14, 1)),
+            hit.target
+        );
+        assert_close(6.0, hit.distance);
+        assert_eq!(-EntityWorldPosition::X, hit.normal);
+    }
+}
@@ -1,10 +1,19 @@
-use crate::{Drag, Gravity};
-use game_lib::bevy::prelude::*;
+use crate::{
+    bodies::AxisAlignedBoundingBox,
+    broad_phase::{BroadPhase, Entry, QuadTree, SpatialHash},
+    Drag, Gravity,
+};
+use game_lib::bevy::{prelude::*, utils::HashMap};
+use game_tiles::EntityWorldRect;
 use std::time::Duration;
 
 #[derive(Clone, Debug, Reflect)]
 pub struct PhysicsState {
+    /// Accumulates frame time into fixed-size physics steps. The time left
+    /// over after the last finished step is used to interpolate transforms.
     pub step_timer: Timer,
+
+    /// Number of steps that still need to be simulated this frame.
     pub queued_steps: u32,
 
     /// Global gravitational acceleration in `m/s^2`. This can be overriden
@@ -14,6 +23,26 @@ pub struct PhysicsState {
     /// Global drag coefficient. Drag is not calculated using the shape of the
     /// body, so this will always be multiplied by the square velocity.
     pub drag: Drag,
+
+    /// Whether the simulation should be deterministic. Bodies are stepped one
+    /// at a time in a stable order and collision events are sorted, so
+    /// replaying the same inputs produces identical results.
+    pub deterministic: bool,
+
+    /// Speed in `m/s` below which a body is considered idle.
+    pub sleep_velocity: f32,
+
+    /// Number of consecutive steps a body needs to be idle for before it is
+    /// put to sleep.
+    pub sleep_steps: u32,
+
+    /// Fraction of a body that needs to be submerged in fluids for it to be
+    /// able to swim.
+    pub swim_submersion: f32,
+
+    /// Broad phase used to index bodies. This is only read when entering a
+    /// world.
+    pub broad_phase: BroadPhaseConfig,
 }
 
 impl Default for PhysicsState {
@@ -24,6 +53,141 @@ impl Default for PhysicsState {
             queued_steps: Default::default(),
             gravity: Default::default(),
             drag: Default::default(),
+            deterministic: false,
+            sleep_velocity: 0.1,
+            sleep_steps: 30,
+            swim_submersion: 0.5,
+            broad_phase: Default::default(),
         }
     }
 }
+
+/// Which broad phase to index bodies with.
+#[derive(Clone, Copy, PartialEq, Debug, Reflect)]
+pub enum BroadPhaseConfig {
+    /// A quadtree with fixed root bounds centered on the origin. Bodies
+    /// outside of the root bounds are always returned by queries.
+    QuadTree,
+
+    /// A uniform grid with square cells of the given size in meters.
+    SpatialHash { cell_size: f32 },
+}
+
+impl Default for BroadPhaseConfig {
+    fn default() -> Self {
+        BroadPhaseConfig::QuadTree
+    }
+}
+
+#[derive(Clone, Debug)]
+enum BodyIndex {
+    QuadTree(QuadTree<Entity, 4, 16, 8>),
+    SpatialHash(SpatialHash<Entity>),
+}
+
+/// Spatial index of all the bodies registered with the physics engine.
+#[derive(Clone, Debug)]
+pub struct BodyTree {
+    index: BodyIndex,
+    entries: HashMap<Entity, Entry>,
+}
+
+impl BodyTree {
+    /// Bodies outside of this area are still indexed by the quadtree, but are
+    /// always returned by queries.
+    const INDEXED_SIZE: f32 = 8192.0;
+
+    pub fn new(config: BroadPhaseConfig) -> Self {
+        let index = match config {
+            BroadPhaseConfig::QuadTree => BodyIndex::QuadTree(QuadTree::new(
+                AxisAlignedBoundingBox::from_center(Vec2::ZERO, Vec2::ONE * Self::INDEXED_SIZE),
+            )),
+            BroadPhaseConfig::SpatialHash { cell_size } => {
+                BodyIndex::SpatialHash(SpatialHash::new(cell_size))
+            }
+        };
+
+        BodyTree {
+            index,
+            entries: HashMap::default(),
+        }
+    }
+
+    /// Adds a body to the index, or updates its bounds if it's already indexed.
+    pub fn update(&mut self, entity: Entity, bounds: EntityWorldRect) {
+        let bounds = AxisAlignedBoundingBox::new(bounds.bottom_left.into(), bounds.size.into());
+        match (self.entries.get(&entity), &mut self.index) {
+            (Some(&entry), BodyIndex::QuadTree(tree)) => {
+                tree.set_bounds(entry, bounds);
+            }
+            (Some(&entry), BodyIndex::SpatialHash(hash)) => {
+                hash.set_bounds(entry, bounds);
+            }
+            (None, BodyIndex::QuadTree(tree)) => {
+                let entry = tree.insert(entity, bounds);
+                self.entries.insert(entity, entry);
+            }
+            (None, BodyIndex::SpatialHash(hash)) => {
+                let entry = hash.insert(entity, bounds);
+                self.entries.insert(entity, entry);
+            }
+        }
+    }
+
+    /// Removes a body from the index.
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(entry) = self.entries.remove(&entity) {
+            match &mut self.index {
+                BodyIndex::QuadTree(tree) => {
+                    tree.remove(entry);
+                }
+                BodyIndex::SpatialHash(hash) => {
+                    hash.remove(entry);
+                }
+            }
+        }
+    }
+
+    /// Gets the bodies which may intersect the given bounds.
+    pub fn query_bounds(&self, bounds: EntityWorldRect) -> impl Iterator<Item = Entity> + '_ {
+        let bounds = AxisAlignedBoundingBox::new(bounds.bottom_left.into(), bounds.size.into());
+        let entities: Box<dyn Iterator<Item = Option<&Entity>> + '_> = match &self.index {
+            BodyIndex::QuadTree(tree) => Box::new(
+                tree.query_bounds(bounds)
+                    .into_iter()
+                    .map(move |entry| tree.get(entry)),
+            ),
+            BodyIndex::SpatialHash(hash) => Box::new(
+                hash.query_bounds(bounds)
+                    .into_iter()
+                    .map(move |entry| hash.get(entry)),
+            ),
+        };
+
+        entities.flatten().copied()
+    }
+
+    /// Gets the bodies which may contain the given point.
+    pub fn query_point(&self, point: Vec2) -> impl Iterator<Item = Entity> + '_ {
+        let entities: Box<dyn Iterator<Item = Option<&Entity>> + '_> = match &self.index {
+            BodyIndex::QuadTree(tree) => Box::new(
+                tree.query_point(point)
+                    .into_iter()
+                    .map(move |entry| tree.get(entry)),
+            ),
+            BodyIndex::SpatialHash(hash) => Box::new(
+                hash.query_point(point)
+                    .into_iter()
+                    .map(move |entry| hash.get(entry)),
+            ),
+        };
+
+        entities.flatten().copied()
+    }
+}
+
+impl Default for BodyTree {
+    fn default() -> Self {
+        BodyTree::new(BroadPhaseConfig::default())
+    }
+}
@@ -0,0 +1,726 @@
+use crate::{PhysicsMaterial, TileCollisionShape, TilePhysicsExt};
+use game_lib::bevy::prelude::Entity;
+use game_tiles::{
+    EntityWorldPosition, EntityWorldRect, GameWorld, Tile, TileWorldPosition, TileWorldRect,
+};
+
+/// How close two surfaces need to be to be considered touching.
+pub const CONTACT_EPSILON: f32 = 0.001;
+
+/// Steepest slope (rise over run) that bodies can walk up or down.
+pub const MAX_SLOPE: f32 = 1.0;
+
+/// Bodies which would bounce off of a surface slower than this come to rest
+/// on it instead.
+pub const MIN_BOUNCE_VELOCITY: f32 = 1.0;
+
+/// A non-kinematic body that kinematic bodies collide with while moving.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SolidBody {
+    pub entity: Entity,
+    pub bounds: EntityWorldRect,
+    pub material: PhysicsMaterial,
+}
+
+/// The first contact between a body swept along an axis and the tile grid or
+/// the solid bodies in the world.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SweepHit {
+    /// Fraction of the offset travelled before contact, in the range `[0, 1]`.
+    pub time: f32,
+
+    /// Normal of the surface that was hit.
+    pub normal: EntityWorldPosition,
+
+    /// Every tile touched at the time of impact.
+    pub tiles: Vec<(Tile, TileWorldPosition)>,
+
+    /// Every solid body touched at the time of impact.
+    pub bodies: Vec<SolidBody>,
+}
+
+/// The result of moving a body through the tile grid for a single step.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BodyMovement {
+    pub bounds: EntityWorldRect,
+    pub velocity: EntityWorldPosition,
+
+    /// Contact made while moving along the x-axis. The time of impact is
+    /// relative to the full horizontal offset of the step.
+    pub x_hit: Option<SweepHit>,
+
+    /// Contact made while moving along the y-axis, including the ground a
+    /// body was kept on while walking down a slope.
+    pub y_hit: Option<SweepHit>,
+}
+
+/// Sweeps a body horizontally through the tile grid, returning the first
+/// contact with a tile in its way. Only the faces of tiles the body would
+/// enter are considered, so slopes rising in the direction of movement are
+/// never hit.
+pub fn sweep_x(world: &GameWorld, bounds: EntityWorldRect, offset: f32) -> Option<SweepHit> {
+    if offset == 0.0 {
+        return None;
+    }
+
+    // Side of the tile being entered, relative to the left of the tile
+    let (leading, entered_side) = if offset > 0.0 {
+        (bounds.right(), 0.0)
+    } else {
+        (bounds.left(), 1.0)
+    };
+
+    let swept = EntityWorldRect::new(
+        EntityWorldPosition::new(bounds.left().min(bounds.left() + offset), bounds.bottom()),
+        EntityWorldPosition::new(bounds.width() + offset.abs(), bounds.height()),
+    );
+
+    get_tiles(world, TileWorldRect::from(swept))
+        .filter_map(|(tile, position)| {
+            let shape = tile.collision_shape();
+            if shape == TileCollisionShape::OneWay {
+                return None;
+            }
+
+            // Only tiles ahead of the body can be entered
+            let face = position.x as f32 + entered_side;
+            let time = (face - leading) / offset;
+            if time < -CONTACT_EPSILON / offset.abs() || time > 1.0 {
+                return None;
+            }
+
+            // Check if the entered face overlaps the body vertically
+            let top = position.y as f32 + shape.surface_height(entered_side, entered_side)?;
+            let overlaps = bounds.bottom() < top - CONTACT_EPSILON
+                && bounds.top() > position.y as f32 + CONTACT_EPSILON;
+            overlaps.then(|| (time.max(0.0), tile, position))
+        })
+        .fold(None, |hit, (time, tile, position)| {
+            let contact = SweepHit {
+                time,
+                normal: -EntityWorldPosition::X * offset.signum(),
+                tiles: vec![(tile, position)],
+                ..Default::default()
+            };
+            merge(hit, Some(contact))
+        })
+}
+
+/// Sweeps a body vertically through the tile grid, returning the first
+/// contact with a tile in its way. One-way platforms are only landed on if
+/// the body starts above them and isn't dropping through them.
+pub fn sweep_y(
+    world: &GameWorld,
+    bounds: EntityWorldRect,
+    offset: f32,
+    drop_through: bool,
+) -> Option<SweepHit> {
+    if offset == 0.0 {
+        return None;
+    }
+
+    let swept = EntityWorldRect::new(
+        EntityWorldPosition::new(bounds.left(), bounds.bottom().min(bounds.bottom() + offset)),
+        EntityWorldPosition::new(bounds.width(), bounds.height() + offset.abs()),
+    );
+
+    get_tiles(world, TileWorldRect::from(swept))
+        .filter_map(|(tile, position)| {
+            let shape = tile.collision_shape();
+            let top = shape.surface_top(position, bounds)?;
+            let time = if offset < 0.0 {
+                // Falling onto the surface of the tile
+                if drop_through && shape == TileCollisionShape::OneWay
+                    || top > bounds.bottom() + CONTACT_EPSILON
+                {
+                    return None;
+                }
+
+                (bounds.bottom() - top) / -offset
+            } else {
+                // Rising into the bottom of the tile
+                let bottom = position.y as f32;
+                if shape == TileCollisionShape::OneWay || bottom < bounds.top() - CONTACT_EPSILON {
+                    return None;
+                }
+
+                (bottom - bounds.top()) / offset
+            };
+
+            (time <= 1.0).then(|| (time.max(0.0), tile, position))
+        })
+        .fold(None, |hit, (time, tile, position)| {
+            let contact = SweepHit {
+                time,
+                normal: -EntityWorldPosition::Y * offset.signum(),
+                tiles: vec![(tile, position)],
+                ..Default::default()
+            };
+            merge(hit, Some(contact))
+        })
+}
+
+/// Sweeps a body along an axis against the bounds of solid bodies, returning
+/// the first contact with one in its way. Solid bodies the body already
+/// overlaps are ignored so that it can be pushed out of them instead.
+pub fn sweep_bodies(
+    solids: &[SolidBody],
+    bounds: EntityWorldRect,
+    offset: f32,
+    vertical: bool,
+) -> Option<SweepHit> {
+    if offset == 0.0 {
+        return None;
+    }
+
+    let overlaps = |min: f32, max: f32, other_min: f32, other_max: f32| {
+        min < other_max - CONTACT_EPSILON && max > other_min + CONTACT_EPSILON
+    };
+
+    solids
+        .iter()
+        .filter_map(|&solid| {
+            // Project both bodies onto the axis of movement, and make sure they
+            // overlap across it
+            let other = solid.bounds;
+            let (min, max, other_min, other_max) = if vertical {
+                if !overlaps(bounds.left(), bounds.right(), other.left(), other.right()) {
+                    return None;
+                }
+
+                (bounds.bottom(), bounds.top(), other.bottom(), other.top())
+            } else {
+                if !overlaps(bounds.bottom(), bounds.top(), other.bottom(), other.top()) {
+                    return None;
+                }
+
+                (bounds.left(), bounds.right(), other.left(), other.right())
+            };
+
+            let gap = if offset > 0.0 {
+                other_min - max
+            } else {
+                min - other_max
+            };
+            let time = gap / offset.abs();
+            (gap >= -CONTACT_EPSILON && time <= 1.0).then(|| (time.max(0.0), solid))
+        })
+        .fold(None, |hit, (time, solid)| {
+            let axis = if vertical {
+                EntityWorldPosition::Y
+            } else {
+                EntityWorldPosition::X
+            };
+            let contact = SweepHit {
+                time,
+                normal: -axis * offset.signum(),
+                bodies: vec![solid],
+                ..Default::default()
+            };
+            merge(hit, Some(contact))
+        })
+}
+
+/// Moves a body through the tile grid for a step of `delta` seconds, stopping
+/// it at the exact point it contacts a tile or solid body on each axis. The
+/// body's velocity is then resolved against the materials of everything it
+/// touched. Bodies with a non-finite offset are left where they are with no
+/// velocity.
+pub fn move_body(
+    world: &GameWorld,
+    solids: &[SolidBody],
+    bounds: EntityWorldRect,
+    velocity: EntityWorldPosition,
+    delta: f32,
+    drop_through: bool,
+    material: PhysicsMaterial,
+) -> BodyMovement {
+    let target_offset = velocity * delta;
+    if !target_offset.is_finite() {
+        return BodyMovement {
+            bounds,
+            velocity: EntityWorldPosition::ZERO,
+            x_hit: None,
+            y_hit: None,
+        };
+    }
+
+    let mut next_bounds = bounds;
+    let mut next_velocity = velocity;
+
+    // Bodies resting on the ground at the start of the step follow slopes
+    // down rather than walking off of them
+    let grounded = find_ground(world, bounds, 0.0, drop_through).is_some();
+
+    // Move along the x-axis one tile column at a time so that slopes can lift
+    // the body between columns. The leading edge crosses at most one column
+    // boundary per segment, which bounds the number of segments.
+    let mut remaining = target_offset.x;
+    let mut x_hit = None;
+    let max_segments = target_offset.x.abs().ceil() as u32 + 1;
+    for _ in 0..max_segments {
+        if remaining == 0.0 {
+            break;
+        }
+
+        let leading = if remaining > 0.0 {
+            next_bounds.right()
+        } else {
+            next_bounds.left()
+        };
+        let boundary = if remaining > 0.0 {
+            leading.floor() + 1.0
+        } else {
+            leading.ceil() - 1.0
+        };
+        let segment = if (boundary - leading).abs() < remaining.abs() {
+            boundary - leading
+        } else {
+            remaining
+        };
+
+        // Far from the origin, the next column boundary may round to the
+        // leading edge itself and the body can't move any further
+        if segment == 0.0 {
+            break;
+        }
+
+        // Stop at walls
+        let travelled = target_offset.x - remaining;
+        let hit = merge(
+            sweep_x(world, next_bounds, segment),
+            sweep_bodies(solids, next_bounds, segment, false),
+        );
+        if let Some(hit) = hit {
+            next_bounds.bottom_left.x += segment * hit.time;
+            x_hit = Some(SweepHit {
+                time: (travelled + segment * hit.time) / target_offset.x,
+                ..hit
+            });
+            break;
+        }
+
+        // Walk up slopes if there's room for the body at the top of the slope
+        let step = next_bounds.offset(EntityWorldPosition::X * segment);
+        match get_overlap(world, step) {
+            None => next_bounds = step,
+            Some(top) => {
+                let mut climbed = step;
+                climbed.bottom_left.y = top;
+                let steep = top - step.bottom() > segment.abs() * MAX_SLOPE + CONTACT_EPSILON;
+                if steep || get_overlap(world, climbed).is_some() {
+                    x_hit = Some(SweepHit {
+                        time: travelled / target_offset.x,
+                        normal: -EntityWorldPosition::X * segment.signum(),
+                        tiles: get_tiles(world, TileWorldRect::from(step)).collect(),
+                        bodies: Vec::new(),
+                    });
+                    break;
+                }
+
+                next_bounds = climbed;
+            }
+        }
+
+        remaining -= segment;
+    }
+
+    let moved_x = next_bounds.left() - bounds.left();
+    if let Some(hit) = &x_hit {
+        let (x, y) = resolve_contact(next_velocity.x, next_velocity.y, hit, material);
+        next_velocity = EntityWorldPosition::new(x, y);
+    }
+
+    // Move along the y-axis
+    let mut y_hit = merge(
+        sweep_y(world, next_bounds, target_offset.y, drop_through),
+        sweep_bodies(solids, next_bounds, target_offset.y, true),
+    );
+    match &y_hit {
+        Some(hit) => {
+            next_bounds.bottom_left.y += target_offset.y * hit.time;
+            let (y, x) = resolve_contact(next_velocity.y, next_velocity.x, hit, material);
+            next_velocity = EntityWorldPosition::new(x, y);
+        }
+        None => next_bounds.bottom_left.y += target_offset.y,
+    }
+
+    // Keep bodies walking down slopes on the ground instead of letting them
+    // bounce down the slope
+    if grounded && y_hit.is_none() && next_velocity.y <= 0.0 && moved_x != 0.0 {
+        let snap_distance = moved_x.abs() * MAX_SLOPE;
+        if let Some((top, tile, position)) =
+            find_ground(world, next_bounds, snap_distance, drop_through)
+        {
+            let hit = SweepHit {
+                time: 1.0,
+                normal: EntityWorldPosition::Y,
+                tiles: vec![(tile, position)],
+                bodies: Vec::new(),
+            };
+            let (y, x) = resolve_contact(next_velocity.y, next_velocity.x, &hit, material);
+            next_bounds.bottom_left.y = top;
+            next_velocity = EntityWorldPosition::new(x, y);
+            y_hit = Some(hit);
+        }
+    }
+
+    BodyMovement {
+        bounds: next_bounds,
+        velocity: next_velocity,
+        x_hit,
+        y_hit,
+    }
+}
+
+/// Resolves the velocity of a body which made contact with some surfaces,
+/// returning its new velocity into and along them. The body bounces off of
+/// the surface based on the restitution of the materials, and friction slows
+/// it along the surface based on how much it was slowed into it.
+fn resolve_contact(
+    normal_velocity: f32,
+    tangent_velocity: f32,
+    hit: &SweepHit,
+    material: PhysicsMaterial,
+) -> (f32, f32) {
+    // Average the combined materials of everything touched
+    let count = (hit.tiles.len() + hit.bodies.len()).max(1) as f32;
+    let tile_materials = hit.tiles.iter().map(|&(tile, _)| tile.material());
+    let body_materials = hit.bodies.iter().map(|solid| solid.material);
+    let (friction, restitution) = tile_materials
+        .chain(body_materials)
+        .map(|other| material.combine(other))
+        .fold((0.0, 0.0), |(friction, restitution), combined| {
+            (
+                friction + combined.friction / count,
+                restitution + combined.restitution / count,
+            )
+        });
+
+    // Bounce off of the surface
+    let bounce = -normal_velocity * restitution;
+    let bounce = if bounce.abs() < MIN_BOUNCE_VELOCITY {
+        0.0
+    } else {
+        bounce
+    };
+
+    // Apply friction proportional to the impulse from the surface
+    let impulse = (bounce - normal_velocity).abs();
+    let slowed = (tangent_velocity.abs() - friction * impulse).max(0.0);
+    (bounce, slowed.copysign(tangent_velocity))
+}
+
+/// Finds the highest tile surface under the body which is at most
+/// `max_distance` below the bottom of it.
+pub fn find_ground(
+    world: &GameWorld,
+    bounds: EntityWorldRect,
+    max_distance: f32,
+    drop_through: bool,
+) -> Option<(f32, Tile, TileWorldPosition)> {
+    let search_bounds = EntityWorldRect::new(
+        EntityWorldPosition::new(
+            bounds.left(),
+            bounds.bottom() - max_distance - CONTACT_EPSILON,
+        ),
+        EntityWorldPosition::new(bounds.width(), max_distance + CONTACT_EPSILON * 2.0),
+    );
+
+    get_tiles(world, TileWorldRect::from(search_bounds))
+        .filter_map(|(tile, position)| {
+            let shape = tile.collision_shape();
+            if drop_through && shape == TileCollisionShape::OneWay {
+                return None;
+            }
+
+            shape
+                .surface_top(position, bounds)
+                .filter(|&top| {
+                    top <= bounds.bottom() + CONTACT_EPSILON
+                        && top >= bounds.bottom() - max_distance - CONTACT_EPSILON
+                })
+                .map(|top| (top, tile, position))
+        })
+        .fold(
+            None,
+            |highest: Option<(f32, Tile, TileWorldPosition)>, cur| match highest {
+                Some(highest) if highest.0 >= cur.0 => Some(highest),
+                _ => Some(cur),
+            },
+        )
+}
+
+/// Gets the highest surface of the solid tiles overlapping the given bounds,
+/// if any do. One-way platforms never overlap a body.
+fn get_overlap(world: &GameWorld, bounds: EntityWorldRect) -> Option<f32> {
+    get_tiles(world, TileWorldRect::from(bounds))
+        .filter_map(|(tile, position)| {
+            let shape = tile.collision_shape();
+            if shape == TileCollisionShape::OneWay {
+                return None;
+            }
+
+            shape
+                .surface_top(position, bounds)
+                .filter(|&top| top > bounds.bottom() + CONTACT_EPSILON)
+        })
+        .fold(None, |highest: Option<f32>, top| {
+            Some(highest.map_or(top, |highest| highest.max(top)))
+        })
+}
+
+fn get_tiles(
+    world: &GameWorld,
+    rect: TileWorldRect,
+) -> impl Iterator<Item = (Tile, TileWorldPosition)> + '_ {
+    rect.iter_positions().flat_map(move |position| {
+        world
+            .get_tile(position)
+            .into_iter()
+            .copied()
+            .flatten()
+            .map(move |tile| (tile, position))
+    })
+}
+
+/// Keeps the earliest of two hits, merging them if they happen at the same
+/// time.
+fn merge(hit: Option<SweepHit>, other: Option<SweepHit>) -> Option<SweepHit> {
+    match (hit, other) {
+        (Some(mut hit), Some(other)) if (hit.time - other.time).abs() <= f32::EPSILON => {
+            hit.tiles.extend(other.tiles);
+            hit.bodies.extend(other.bodies);
+            Some(hit)
+        }
+        (Some(hit), Some(other)) if other.time < hit.time => Some(other),
+        (Some(hit), _) => Some(hit),
+        (None, other) => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{assert_close, flat_world, rect};
+
+    const DELTA: f32 = 1.0 / 30.0;
+
+    /// Moves a body with the default material.
+    fn step(
+        world: &GameWorld,
+        bounds: EntityWorldRect,
+        velocity: EntityWorldPosition,
+        delta: f32,
+    ) -> BodyMovement {
+        move_body(
+            world,
+            &[],
+            bounds,
+            velocity,
+            delta,
+            false,
+            PhysicsMaterial::default(),
+        )
+    }
+
+    #[test]
+    fn fast_body_does_not_tunnel_through_floor() {
+        let world = flat_world(&[]);
+        let bounds = rect(0.25, 10.0, 0.5, 0.5);
+        let velocity = EntityWorldPosition::new(0.0, -1000.0);
+        let movement = step(&world, bounds, velocity, 1.0);
+
+        assert_close(1.0, movement.bounds.bottom());
+        assert_close(0.0, movement.velocity.y);
+        let hit = movement.y_hit.expect("body should land on the floor");
+        assert_eq!(EntityWorldPosition::Y, hit.normal);
+        assert_close(9.0 / 1000.0, hit.time);
+    }
+
+    #[test]
+    fn fast_body_does_not_tunnel_through_wall() {
+        let world = flat_world(&[(Tile::Stone, 20, 1)]);
+        let bounds = rect(0.0, 1.0, 0.25, 0.5);
+        let velocity = EntityWorldPosition::new(500.0, 0.0);
+        let movement = step(&world, bounds, velocity, 1.0);
+
+        assert_close(19.75, movement.bounds.left());
+        assert_close(0.0, movement.velocity.x);
+        let hit = movement.x_hit.expect("body should hit the wall");
+        assert_eq!(-EntityWorldPosition::X, hit.normal);
+        assert_eq!(
+            vec![(Tile::Stone, TileWorldPosition::new(20, 1))],
+            hit.tiles
+        );
+        assert_close(19.75 / 500.0, hit.time);
+    }
+
+    #[test]
+    fn thin_body_hits_ceiling_at_exact_offset() {
+        // A body taller than it is wide must be stopped by its height, not
+        // its width
+        let world = flat_world(&[(Tile::Stone, 0, 6)]);
+        let bounds = rect(0.25, 1.0, 0.5, 2.0);
+        let velocity = EntityWorldPosition::new(0.0, 10.0);
+        let movement = step(&world, bounds, velocity, 1.0);
+
+        assert_close(4.0, movement.bounds.bottom());
+        let hit = movement.y_hit.expect("body should hit the ceiling");
+        assert_eq!(-EntityWorldPosition::Y, hit.normal);
+        assert_close(0.3, hit.time);
+    }
+
+    #[test]
+    fn sweep_x_ignores_tiles_behind_body() {
+        let world = flat_world(&[(Tile::Stone, -1, 1)]);
+        let bounds = rect(0.0, 1.0, 0.5, 0.5);
+
+        assert_eq!(None, sweep_x(&world, bounds, 0.4));
+        let hit = sweep_x(&world, bounds, -0.4).expect("body should be touching the wall");
+        assert_close(0.0, hit.time);
+        assert_eq!(EntityWorldPosition::X, hit.normal);
+    }
+
+    #[test]
+    fn sweep_y_lands_on_half_slab() {
+        let world = flat_world(&[(Tile::StoneSlab, 0, 1)]);
+        let bounds = rect(0.25, 3.0, 0.5, 0.5);
+        let hit = sweep_y(&world, bounds, -4.0, false).expect("body should land on the slab");
+
+        assert_close(1.5 / 4.0, hit.time);
+        assert_eq!(
+            vec![(Tile::StoneSlab, TileWorldPosition::new(0, 1))],
+            hit.tiles
+        );
+    }
+
+    #[test]
+    fn one_way_platform_only_stops_falling_bodies() {
+        let world = flat_world(&[(Tile::WoodPlatform, 0, 4)]);
+        let below = rect(0.25, 2.0, 0.5, 0.5);
+        let above = rect(0.25, 7.0, 0.5, 0.5);
+
+        assert_eq!(None, sweep_y(&world, below, 5.0, false));
+        assert!(sweep_y(&world, above, -5.0, false).is_some());
+        assert_eq!(None, sweep_y(&world, above, -5.0, true));
+    }
+
+    #[test]
+    fn body_walks_up_slope() {
+        let world = flat_world(&[(Tile::StoneSlopeRight, 2, 1), (Tile::Stone, 3, 1)]);
+        let bounds = rect(1.0, 1.0, 0.5, 0.5);
+        let velocity = EntityWorldPosition::new(2.5, 0.0);
+        let movement = step(&world, bounds, velocity, 1.0);
+
+        assert_eq!(None, movement.x_hit);
+        assert_close(3.5, movement.bounds.left());
+        assert_close(2.0, movement.bounds.bottom());
+    }
+
+    #[test]
+    fn replaying_steps_is_bit_exact() {
+        let world = flat_world(&[(Tile::StoneSlopeRight, 4, 1), (Tile::Stone, 8, 1)]);
+        let simulate = || {
+            let mut bounds = rect(0.0, 6.0, 0.8, 1.4);
+            let mut velocity = EntityWorldPosition::new(3.0, 0.0);
+            (0..120)
+                .map(|_| {
+                    velocity.y -= 9.81 * DELTA;
+                    let movement = step(&world, bounds, velocity, DELTA);
+                    bounds = movement.bounds;
+                    velocity = movement.velocity;
+                    (
+                        bounds.left().to_bits(),
+                        bounds.bottom().to_bits(),
+                        velocity.x.to_bits(),
+                        velocity.y.to_bits(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(simulate(), simulate());
+    }
+
+    #[test]
+    fn non_finite_velocity_does_not_move_body() {
+        let world = flat_world(&[]);
+        let bounds = rect(0.25, 5.0, 0.5, 0.5);
+        for &x in &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let velocity = EntityWorldPosition::new(x, 0.0);
+            let movement = step(&world, bounds, velocity, DELTA);
+
+            assert_eq!(bounds, movement.bounds);
+            assert_eq!(EntityWorldPosition::ZERO, movement.velocity);
+        }
+    }
+
+    #[test]
+    fn body_far_from_origin_stops_moving() {
+        // Consecutive floats are 4 apart here, so the next column boundary
+        // rounds back to the body's edge
+        let world = flat_world(&[]);
+        let bounds = rect(33_554_432.0, 5.0, 4.0, 4.0);
+        let velocity = EntityWorldPosition::new(30.0, 0.0);
+        let movement = step(&world, bounds, velocity, 1.0);
+
+        assert_eq!(bounds.left(), movement.bounds.left());
+    }
+
+    #[test]
+    fn body_bounces_off_slime() {
+        let world = flat_world(&[(Tile::Slime, 0, 1)]);
+        let bounds = rect(0.25, 5.0, 0.5, 0.5);
+        let movement = step(&world, bounds, EntityWorldPosition::new(0.0, -10.0), 1.0);
+
+        assert_close(2.0, movement.bounds.bottom());
+        assert_close(8.0, movement.velocity.y);
+    }
+
+    #[test]
+    fn ice_is_more_slippery_than_stone() {
+        let ice = flat_world(&[(Tile::Ice, 0, 1), (Tile::Ice, 1, 1), (Tile::Ice, 2, 1)]);
+        let stone = flat_world(&[
+            (Tile::Stone, 0, 1),
+            (Tile::Stone, 1, 1),
+            (Tile::Stone, 2, 1),
+        ]);
+        let bounds = rect(0.0, 2.0, 0.5, 0.5);
+        let velocity = EntityWorldPosition::new(5.0, -9.81 * DELTA);
+        let on_ice = step(&ice, bounds, velocity, DELTA);
+        let on_stone = step(&stone, bounds, velocity, DELTA);
+
+        assert!(on_stone.velocity.x < 5.0);
+        assert!(on_ice.velocity.x > on_stone.velocity.x);
+        assert!(on_ice.velocity.x < 5.0);
+    }
+
+    #[test]
+    fn body_lands_on_solid_body() {
+        let world = flat_world(&[]);
+        let platform = SolidBody {
+            entity: Entity::new(0),
+            bounds: rect(0.0, 3.0, 2.0, 0.5),
+            material: PhysicsMaterial::default(),
+        };
+        let bounds = rect(0.25, 5.0, 0.5, 0.5);
+        let velocity = EntityWorldPosition::new(0.0, -10.0);
+        let movement = move_body(
+            &world,
+            &[platform],
+            bounds,
+            velocity,
+            1.0,
+            false,
+            PhysicsMaterial::default(),
+        );
+
+        assert_close(3.5, movement.bounds.bottom());
+        assert_close(0.0, movement.velocity.y);
+        let hit = movement.y_hit.expect("body should land on the platform");
+        assert_eq!(EntityWorldPosition::Y, hit.normal);
+        assert_eq!(vec![platform], hit.bodies);
+        assert!(hit.tiles.is_empty());
+    }
+}
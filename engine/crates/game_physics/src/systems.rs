@@ -1,23 +1,35 @@
 use crate::{
-    Acceleration, BodyType, Drag, Forces, Gravity, JumpStatus, Mass, PhysicsState, TileCollision,
-    TileCollisionAxis, Velocity,
+    find_ground, move_body, sweep_x, sweep_y, Acceleration, BodyTree, BodyType, Drag,
+    DropThroughPlatforms, Fluid, Forces, Gravity, IdleSteps, JumpStatus, Mass, MoveDirection,
+    MovementConfig, PhysicsMaterial, PhysicsState, PlatformPath, PreviousBounds, Sleeping,
+    SolidBody, StandingOn, Submerged, TileCollision, TileCollisionAxis, TilePhysicsExt, Velocity,
+    CONTACT_EPSILON,
 };
 use game_lib::{
-    bevy::{ecs::schedule::ShouldRun, prelude::*, tasks::ComputeTaskPool},
+    bevy::{ecs::schedule::ShouldRun, prelude::*, tasks::ComputeTaskPool, utils::HashSet},
     tracing::{self, instrument},
 };
-use game_tiles::{EntityWorldPosition, EntityWorldRect, GameWorld, TileWorldRect};
+use game_tiles::{EntityWorldPosition, EntityWorldRect, GameWorld, TileChanged, TileWorldRect};
 
 #[instrument(skip(commands, state))]
 pub fn setup(mut commands: Commands, state: Option<Res<PhysicsState>>) {
-    if state.is_none() {
-        commands.insert_resource(PhysicsState::default());
-    }
+    let broad_phase = match state {
+        Some(state) => state.broad_phase,
+        None => {
+            let state = PhysicsState::default();
+            let broad_phase = state.broad_phase;
+            commands.insert_resource(state);
+            broad_phase
+        }
+    };
+
+    commands.insert_resource(BodyTree::new(broad_phase));
 }
 
 #[instrument(skip(commands))]
 pub fn cleanup(mut commands: Commands) {
     commands.remove_resource::<PhysicsState>();
+    commands.remove_resource::<BodyTree>();
 }
 
 #[instrument(skip(time, state))]
@@ -33,26 +45,34 @@ pub fn update_physics_state(time: Res<Time>, mut state: ResMut<PhysicsState>) {
 }
 
 #[instrument(skip(state))]
-pub fn if_physics_lagged(state: Res<PhysicsState>) -> ShouldRun {
+pub fn if_physics_caught_up(state: Res<PhysicsState>) -> ShouldRun {
     if state.queued_steps > 0 {
-        ShouldRun::Yes
+        ShouldRun::NoAndCheckAgain
     } else {
-        ShouldRun::No
+        ShouldRun::Yes
     }
 }
 
 #[instrument(skip(state, query))]
 pub fn add_kinematic_forces(
     state: Res<PhysicsState>,
-    mut query: Query<(
-        &mut Forces,
-        &Mass,
-        &Velocity,
-        Option<&Gravity>,
-        Option<&Drag>,
-    )>,
+    mut query: Query<
+        (
+            &mut Forces,
+            &Mass,
+            &Velocity,
+            &BodyType,
+            Option<&Gravity>,
+            Option<&Drag>,
+        ),
+        Without<Sleeping>,
+    >,
 ) {
-    for (mut forces, mass, velocity, gravity, drag) in query.iter_mut() {
+    for (mut forces, mass, velocity, &body_type, gravity, drag) in query.iter_mut() {
+        if body_type != BodyType::Kinematic {
+            continue;
+        }
+
         // Weight
         // W = m * g
         let gravity = gravity.map(|x| x.0).unwrap_or(state.gravity.0);
@@ -66,8 +86,163 @@ pub fn add_kinematic_forces(
     }
 }
 
+#[instrument(skip(state, world, query))]
+pub fn add_movement_forces(
+    state: Res<PhysicsState>,
+    world: Res<GameWorld>,
+    mut query: Query<
+        (
+            &EntityWorldRect,
+            &mut Forces,
+            &Mass,
+            &Velocity,
+            &JumpStatus,
+            &MovementConfig,
+            &MoveDirection,
+            Option<&PhysicsMaterial>,
+        ),
+        Without<Sleeping>,
+    >,
+) {
+    let delta = state.step_timer.duration().as_secs_f32();
+    for (&bounds, mut forces, mass, velocity, jump_status, config, direction, material) in
+        query.iter_mut()
+    {
+        let velocity = velocity.0.x;
+        let direction = direction.0.clamp(-1.0, 1.0);
+        let grounded = *jump_status == JumpStatus::OnGround;
+        let acceleration = if direction == 0.0 {
+            if !grounded || velocity == 0.0 {
+                continue;
+            }
+
+            // Slow down based on the friction against the ground, without
+            // overshooting
+            let material = material.copied().unwrap_or_default();
+            let friction = match find_ground(&world, bounds, 0.0, false) {
+                Some((_, tile, _)) => material.combine(tile.material()).friction,
+                None => material.friction,
+            };
+            let deceleration = config.deceleration * friction;
+            -velocity.signum() * deceleration.min(velocity.abs() / delta)
+        } else {
+            let mut acceleration = if grounded {
+                config.ground_acceleration
+            } else {
+                config.air_acceleration
+            };
+            if velocity * direction < 0.0 {
+                acceleration *= config.turn_boost;
+            }
+
+            // Accelerate up to the target speed without overshooting, but
+            // don't slow down bodies that are already moving faster
+            let target = direction * config.max_speed;
+            let remaining = (target - velocity) * direction.signum();
+            if remaining <= 0.0 {
+                continue;
+            }
+
+            direction.signum() * acceleration.min(remaining / delta)
+        };
+
+        // F = m * a
+        forces
+            .0
+            .push(EntityWorldPosition::X * acceleration * mass.0);
+    }
+}
+
+#[instrument(skip(state, world, zones, bodies))]
+pub fn add_fluid_forces(
+    state: Res<PhysicsState>,
+    world: Res<GameWorld>,
+    zones: Query<(&EntityWorldRect, &Fluid), Without<BodyType>>,
+    mut bodies: Query<
+        (
+            &EntityWorldRect,
+            &mut Forces,
+            &Velocity,
+            &BodyType,
+            &mut Submerged,
+            Option<&mut JumpStatus>,
+            Option<&Gravity>,
+        ),
+        Without<Sleeping>,
+    >,
+) {
+    for (bounds, mut forces, velocity, &body_type, mut submerged, jump_status, gravity) in
+        bodies.iter_mut()
+    {
+        let area = bounds.width() * bounds.height();
+        if body_type != BodyType::Kinematic || area <= 0.0 {
+            continue;
+        }
+
+        // Find the fluids the body is in and how much of it is inside each
+        let tile_fluids = TileWorldRect::from(*bounds)
+            .iter_positions()
+            .filter_map(|position| {
+                let fluid = world.get_tile(position).ok()?.as_ref()?.fluid()?;
+                let cell = EntityWorldRect::new(
+                    EntityWorldPosition::new(position.x as f32, position.y as f32),
+                    EntityWorldPosition::ONE,
+                );
+                Some((fluid, cell))
+            });
+        let zone_fluids = zones.iter().map(|(&zone, &fluid)| (fluid, zone));
+        let (submerged_area, buoyancy, drag) = tile_fluids
+            .chain(zone_fluids)
+            .map(|(fluid, rect)| (fluid, overlap_area(*bounds, rect)))
+            .filter(|&(_, overlap)| overlap > 0.0)
+            .fold(
+                (0.0, 0.0, 0.0),
+                |(total, buoyancy, drag), (fluid, overlap)| {
+                    (
+                        total + overlap,
+                        buoyancy + fluid.density * overlap,
+                        drag + fluid.drag * overlap / area,
+                    )
+                },
+            );
+
+        if submerged_area > 0.0 {
+            // Buoyancy
+            // B = p * A * g, pushing against gravity
+            let gravity = gravity.map(|x| x.0).unwrap_or(state.gravity.0);
+            forces.0.push(-gravity * buoyancy);
+
+            // Fluid drag
+            let square_velocity = velocity.0 * velocity.0 * -velocity.0.signum();
+            forces.0.push(square_velocity * drag);
+        }
+
+        let fraction = (submerged_area / area).min(1.0);
+        if submerged.0 != fraction {
+            submerged.0 = fraction;
+        }
+
+        // Let bodies swim while they're deep enough in a fluid
+        if let Some(mut jump_status) = jump_status {
+            let swimming = fraction >= state.swim_submersion;
+            if swimming && *jump_status != JumpStatus::Swimming {
+                *jump_status = JumpStatus::Swimming;
+            } else if !swimming && *jump_status == JumpStatus::Swimming {
+                *jump_status = JumpStatus::InAir { jumps: 0 };
+            }
+        }
+    }
+}
+
+/// Gets the area of the overlap between two rectangles.
+fn overlap_area(a: EntityWorldRect, b: EntityWorldRect) -> f32 {
+    let width = a.right().min(b.right()) - a.left().max(b.left());
+    let height = a.top().min(b.top()) - a.bottom().max(b.bottom());
+    width.max(0.0) * height.max(0.0)
+}
+
 #[instrument(skip(query))]
-pub fn apply_forces(mut query: Query<(&mut Forces, &mut Acceleration, &Mass)>) {
+pub fn apply_forces(mut query: Query<(&mut Forces, &mut Acceleration, &Mass), Without<Sleeping>>) {
     for (mut forces, mut acceleration, mass) in query.iter_mut() {
         for force in forces.0.drain(..) {
             // a = F/m
@@ -82,11 +257,15 @@ pub fn apply_forces(mut query: Query<(&mut Forces, &mut Acceleration, &Mass)>) {
     }
 }
 
-#[instrument(skip(time, query))]
-pub fn apply_acceleration(time: Res<Time>, mut query: Query<(&Acceleration, &mut Velocity)>) {
+#[instrument(skip(state, query))]
+pub fn apply_acceleration(
+    state: Res<PhysicsState>,
+    mut query: Query<(&Acceleration, &mut Velocity), Without<Sleeping>>,
+) {
+    let delta = state.step_timer.duration().as_secs_f32();
     for (acceleration, mut velocity) in query.iter_mut() {
         // v = a * t
-        velocity.0 += acceleration.0 * time.delta_seconds();
+        velocity.0 += acceleration.0 * delta;
     }
 }
 
@@ -99,13 +278,168 @@ pub fn while_physics_lagged(state: Res<PhysicsState>) -> ShouldRun {
     }
 }
 
+#[instrument(skip(state, world, bodies))]
+pub fn move_platforms(
+    state: Res<PhysicsState>,
+    world: Res<GameWorld>,
+    mut bodies: QuerySet<(
+        Query<(
+            Entity,
+            &mut EntityWorldRect,
+            Option<&mut PreviousBounds>,
+            &mut Velocity,
+            &BodyType,
+            Option<&mut PlatformPath>,
+        )>,
+        Query<
+            (
+                &mut EntityWorldRect,
+                &mut Velocity,
+                &BodyType,
+                Option<&StandingOn>,
+            ),
+            Without<Sleeping>,
+        >,
+    )>,
+) {
+    let delta = state.step_timer.duration().as_secs_f32();
+
+    // Move platforms
+    let mut moved = Vec::new();
+    for (entity, mut bounds, previous_bounds, mut velocity, &body_type, path) in
+        bodies.q0_mut().iter_mut()
+    {
+        if body_type != BodyType::Moving {
+            continue;
+        }
+
+        if let Some(mut path) = path {
+            velocity.0 = follow_path(&mut path, bounds.bottom_left, delta);
+        }
+
+        if let Some(mut previous_bounds) = previous_bounds {
+            previous_bounds.0 = Some(*bounds);
+        }
+
+        let offset = velocity.0 * delta;
+        if offset != EntityWorldPosition::ZERO {
+            *bounds = bounds.offset(offset);
+            moved.push((entity, *bounds, offset, velocity.0));
+        }
+    }
+
+    if moved.is_empty() {
+        return;
+    }
+
+    // Carry the bodies standing on the platforms and push the rest out of the
+    // way
+    for (mut bounds, mut velocity, &body_type, standing_on) in bodies.q1_mut().iter_mut() {
+        if body_type != BodyType::Kinematic {
+            continue;
+        }
+
+        let standing_on = standing_on.and_then(|standing_on| standing_on.0);
+        for &(platform, platform_bounds, offset, platform_velocity) in moved.iter() {
+            if standing_on == Some(platform) {
+                *bounds = carry(&world, *bounds, offset);
+                continue;
+            }
+
+            if !bounds.expand(-CONTACT_EPSILON).intersects(platform_bounds) {
+                continue;
+            }
+
+            // Push the body out along the axis it is least inside the platform
+            let push_x = if offset.x > 0.0 {
+                platform_bounds.right() - bounds.left()
+            } else if offset.x < 0.0 {
+                platform_bounds.left() - bounds.right()
+            } else {
+                f32::INFINITY
+            };
+            let push_y = if offset.y > 0.0 {
+                platform_bounds.top() - bounds.bottom()
+            } else if offset.y < 0.0 {
+                platform_bounds.bottom() - bounds.top()
+            } else {
+                f32::INFINITY
+            };
+
+            if push_x.abs() < push_y.abs() {
+                *bounds = carry(&world, *bounds, EntityWorldPosition::new(push_x, 0.0));
+                if push_x > 0.0 {
+                    velocity.0.x = velocity.0.x.max(platform_velocity.x);
+                } else {
+                    velocity.0.x = velocity.0.x.min(platform_velocity.x);
+                }
+            } else {
+                *bounds = carry(&world, *bounds, EntityWorldPosition::new(0.0, push_y));
+                if push_y > 0.0 {
+                    velocity.0.y = velocity.0.y.max(platform_velocity.y);
+                } else {
+                    velocity.0.y = velocity.0.y.min(platform_velocity.y);
+                }
+            }
+        }
+    }
+}
+
+/// Gets the velocity a platform at `position` needs to follow its path for a
+/// step of `delta` seconds, advancing to the next waypoint once the current
+/// one would be reached.
+fn follow_path(
+    path: &mut PlatformPath,
+    position: EntityWorldPosition,
+    delta: f32,
+) -> EntityWorldPosition {
+    let target = match path.waypoints.get(path.target) {
+        Some(&target) => target,
+        None => return EntityWorldPosition::ZERO,
+    };
+
+    let remaining = target - position;
+    let distance = remaining.x.hypot(remaining.y);
+    if distance <= path.speed * delta {
+        path.target = (path.target + 1) % path.waypoints.len();
+        remaining / delta
+    } else {
+        remaining / distance * path.speed
+    }
+}
+
+/// Moves a body by an offset, stopping it at any tiles in its way.
+fn carry(
+    world: &GameWorld,
+    mut bounds: EntityWorldRect,
+    offset: EntityWorldPosition,
+) -> EntityWorldRect {
+    let time = sweep_x(world, bounds, offset.x).map_or(1.0, |hit| hit.time);
+    bounds.bottom_left.x += offset.x * time;
+    let time = sweep_y(world, bounds, offset.y, false).map_or(1.0, |hit| hit.time);
+    bounds.bottom_left.y += offset.y * time;
+    bounds
+}
+
 #[instrument(skip(pool, state, tile_collisions, world, bodies))]
 pub fn step(
     pool: Res<ComputeTaskPool>,
     mut state: ResMut<PhysicsState>,
     mut tile_collisions: EventWriter<TileCollision>,
     world: Res<GameWorld>,
-    mut bodies: Query<(Entity, &mut EntityWorldRect, &mut Velocity, &BodyType)>,
+    mut bodies: Query<
+        (
+            Entity,
+            &mut EntityWorldRect,
+            Option<&mut PreviousBounds>,
+            &mut Velocity,
+            &BodyType,
+            Option<&DropThroughPlatforms>,
+            Option<&PhysicsMaterial>,
+            Option<&mut StandingOn>,
+        ),
+        Without<Sleeping>,
+    >,
 ) {
     let state = &mut *state;
 
@@ -114,210 +448,261 @@ pub fn step(
         state.queued_steps = queued_steps;
     }
 
+    // Get the bodies kinematic bodies can collide with
+    let mut solids: Vec<_> = bodies
+        .iter_mut()
+        .filter(|&(_, _, _, _, &body_type, ..)| body_type != BodyType::Kinematic)
+        .map(|(entity, bounds, _, _, _, _, material, _)| SolidBody {
+            entity,
+            bounds: *bounds,
+            material: material.copied().unwrap_or_default(),
+        })
+        .collect();
+    solids.sort_by_key(|solid| solid.entity);
+
     let (tile_collisions_tx, tile_collisions_rx) = game_lib::crossbeam::channel::unbounded();
     let state = &*state;
     let world = &*world;
-    info_span!("tile_collisions").in_scope(|| {
-        bodies.par_for_each_mut(
-            &pool,
-            25,
-            |(entity, mut bounds, mut velocity, &body_type)| {
-                // Only step on kinematic bodies
-                if body_type != BodyType::Kinematic {
-                    return;
-                }
-
-                // Calculate unobstructed movement amount
-                let target_offset = velocity.0 * state.step_timer.duration().as_secs_f32();
-                let mut next_bounds = *bounds;
-                let mut next_velocity = velocity.0;
-                const STEP: f32 = 1.0;
-
-                // Get an iterator over the bounds that will be checked along x-axis
-                let x_steps = std::iter::from_fn({
-                    let mut remaining = target_offset.x.abs();
-                    let offset_sign = target_offset.x.signum();
-                    let mut cur_bounds = next_bounds;
-                    move || {
-                        if remaining <= 0.0 {
-                            None
-                        } else if remaining <= STEP {
-                            cur_bounds =
-                                cur_bounds.offset(EntityWorldPosition::X * remaining * offset_sign);
-                            remaining = 0.0;
-                            Some(cur_bounds)
-                        } else {
-                            cur_bounds =
-                                cur_bounds.offset(EntityWorldPosition::X * STEP * offset_sign);
-                            remaining -= STEP;
-                            Some(cur_bounds)
-                        }
-                    }
-                });
-
-                for step in x_steps {
-                    // Get rectangle of tiles to check
-                    let mut checked_tiles = TileWorldRect::from(step);
-                    if velocity.0.x > 0.0 {
-                        checked_tiles.bottom_left.x += checked_tiles.size.x - 1;
-                        checked_tiles.size.x = 1;
-                    } else {
-                        checked_tiles.size.x = 1;
-                    };
-
-                    // Get all collided tiles
-                    let collisions = checked_tiles.iter_positions().flat_map(|position| {
-                        world
-                            .get_tile(position)
-                            .into_iter()
-                            .copied()
-                            .flatten()
-                            .map(move |tile| (tile, position))
-                    });
-
-                    // Resolve collisions
-                    let (collided, step_bounds, step_velocity) = collisions.fold(
-                        (false, step, next_velocity),
-                        |(_, mut next_bounds, mut next_velocity),
-                         (collision_tile, collision_pos)| {
-                            // Send collision event
-                            tile_collisions_tx
-                                .send(TileCollision {
-                                    entity,
-                                    entity_velocity: velocity.0,
-                                    axis: TileCollisionAxis::X,
-                                    tile: collision_tile,
-                                    tile_position: collision_pos,
-                                })
-                                .unwrap();
-
-                            // Update next bounds
-                            next_bounds.bottom_left.x = if velocity.0.x > 0.0 {
-                                collision_pos.x as f32 - next_bounds.width()
-                            } else {
-                                collision_pos.x as f32 + 1.0
-                            };
-
-                            // Update next velocity
-                            next_velocity.x = 0.0;
-
-                            (true, next_bounds, next_velocity)
-                        },
-                    );
-
-                    // Update position and velocity
-                    next_bounds = step_bounds;
-                    next_velocity = step_velocity;
-
-                    // Don't handle anymore x-axis collisions
-                    if collided {
-                        break;
-                    }
-                }
+    let solids = &solids;
+    let step_body = |body: (
+        Entity,
+        Mut<EntityWorldRect>,
+        Option<Mut<PreviousBounds>>,
+        Mut<Velocity>,
+        &BodyType,
+        Option<&DropThroughPlatforms>,
+        Option<&PhysicsMaterial>,
+        Option<Mut<StandingOn>>,
+    )| {
+        let (
+            entity,
+            mut bounds,
+            previous_bounds,
+            mut velocity,
+            &body_type,
+            drop_through,
+            material,
+            standing_on,
+        ) = body;
+
+        // Only step on kinematic bodies
+        if body_type != BodyType::Kinematic {
+            return;
+        }
 
-                let y_steps = std::iter::from_fn({
-                    let mut remaining = target_offset.y.abs();
-                    let offset_sign = target_offset.y.signum();
-                    let mut cur_bounds = next_bounds;
-                    move || {
-                        if remaining <= 0.0 {
-                            None
-                        } else if remaining <= STEP {
-                            cur_bounds =
-                                cur_bounds.offset(EntityWorldPosition::Y * remaining * offset_sign);
-                            remaining = 0.0;
-                            Some(cur_bounds)
-                        } else {
-                            cur_bounds =
-                                cur_bounds.offset(EntityWorldPosition::Y * STEP * offset_sign);
-                            remaining -= STEP;
-                            Some(cur_bounds)
-                        }
-                    }
-                });
-
-                for step in y_steps {
-                    // Get rectangle of tiles to check
-                    let mut checked_tiles = TileWorldRect::from(step);
-                    if velocity.0.y > 0.0 {
-                        checked_tiles.bottom_left.y += checked_tiles.size.y - 1;
-                        checked_tiles.size.y = 1;
-                    } else {
-                        checked_tiles.size.y = 1;
-                    };
-
-                    // Get all collided tiles
-                    let collisions = checked_tiles.iter_positions().flat_map(|position| {
-                        world
-                            .get_tile(position)
-                            .into_iter()
-                            .copied()
-                            .flatten()
-                            .map(move |tile| (tile, position))
-                    });
-
-                    // Resolve collisions
-                    let (collided, step_bounds, step_velocity) = collisions.fold(
-                        (false, step, next_velocity),
-                        |(_, mut next_bounds, mut next_velocity),
-                         (collision_tile, collision_pos)| {
-                            // Send collision event
-                            tile_collisions_tx
-                                .send(TileCollision {
-                                    entity,
-                                    entity_velocity: velocity.0,
-                                    axis: TileCollisionAxis::Y,
-                                    tile: collision_tile,
-                                    tile_position: collision_pos,
-                                })
-                                .unwrap();
-
-                            // Update next bounds
-                            next_bounds.bottom_left.y = if velocity.0.y > 0.0 {
-                                collision_pos.y as f32 - next_bounds.width()
-                            } else {
-                                collision_pos.y as f32 + 1.0
-                            };
-
-                            // Update next velocity
-                            next_velocity.y = 0.0;
-
-                            (true, next_bounds, next_velocity)
-                        },
-                    );
-
-                    // Update position and velocity
-                    next_bounds = step_bounds;
-                    next_velocity = step_velocity;
-
-                    // Don't handle anymore x-axis collisions
-                    if collided {
-                        break;
-                    }
-                }
+        // Keep track of where the body was before this step
+        if let Some(mut previous_bounds) = previous_bounds {
+            previous_bounds.0 = Some(*bounds);
+        }
 
-                // Update entity
-                *bounds = next_bounds;
-                velocity.0 = next_velocity;
-            },
+        // Move the body until it contacts a tile or solid body on each axis
+        let movement = move_body(
+            world,
+            solids,
+            *bounds,
+            velocity.0,
+            state.step_timer.duration().as_secs_f32(),
+            drop_through.map(|x| x.0).unwrap_or(false),
+            material.copied().unwrap_or_default(),
         );
+
+        // Keep track of which moving body the body landed on
+        if let Some(mut standing_on) = standing_on {
+            standing_on.0 = movement
+                .y_hit
+                .as_ref()
+                .filter(|hit| hit.normal == EntityWorldPosition::Y)
+                .and_then(|hit| hit.bodies.first())
+                .map(|solid| solid.entity);
+        }
+
+        // Send collision events
+        let hits = vec![
+            (TileCollisionAxis::X, movement.x_hit),
+            (TileCollisionAxis::Y, movement.y_hit),
+        ];
+        for (axis, hit) in hits {
+            for (tile, tile_position) in hit.into_iter().flat_map(|hit| hit.tiles) {
+                tile_collisions_tx
+                    .send(TileCollision {
+                        entity,
+                        entity_velocity: velocity.0,
+                        axis,
+                        tile,
+                        tile_position,
+                    })
+                    .unwrap();
+            }
+        }
+
+        // Update entity
+        *bounds = movement.bounds;
+        velocity.0 = movement.velocity;
+    };
+
+    info_span!("tile_collisions").in_scope(|| {
+        if state.deterministic {
+            // Step bodies in a stable order
+            let mut bodies: Vec<_> = bodies.iter_mut().collect();
+            bodies.sort_by_key(|&(entity, ..)| entity);
+            bodies.into_iter().for_each(&step_body);
+        } else {
+            bodies.par_for_each_mut(&pool, 25, &step_body);
+        }
     });
 
     drop(tile_collisions_tx);
-    tile_collisions.send_batch(tile_collisions_rx.into_iter());
+    if state.deterministic {
+        // Send collision events in a stable order
+        let mut collisions: Vec<_> = tile_collisions_rx.into_iter().collect();
+        collisions.sort_by_key(|collision| {
+            (
+                collision.entity,
+                collision.axis,
+                collision.tile_position.x,
+                collision.tile_position.y,
+            )
+        });
+        tile_collisions.send_batch(collisions.into_iter());
+    } else {
+        tile_collisions.send_batch(tile_collisions_rx.into_iter());
+    }
 }
 
 #[instrument(skip(collisions, query))]
-pub fn reset_jumps(mut collisions: EventReader<TileCollision>, mut query: Query<&mut JumpStatus>) {
-    for collision in collisions.iter() {
-        if collision.axis == TileCollisionAxis::Y && collision.entity_velocity.y < 0.0 {
-            if let Ok(mut jump_status) = query.get_mut(collision.entity) {
-                *jump_status = JumpStatus::OnGround;
+pub fn update_jump_status(
+    mut collisions: EventReader<TileCollision>,
+    mut query: Query<(Entity, &mut JumpStatus, Option<&StandingOn>), Without<Sleeping>>,
+) {
+    // Get the bodies which landed on a tile this step
+    let landed: HashSet<Entity> = collisions
+        .iter()
+        .filter(|collision| {
+            collision.axis == TileCollisionAxis::Y && collision.entity_velocity.y <= 0.0
+        })
+        .map(|collision| collision.entity)
+        .collect();
+
+    for (entity, mut jump_status, standing_on) in query.iter_mut() {
+        let grounded = landed.contains(&entity)
+            || standing_on.map_or(false, |standing_on| standing_on.0.is_some());
+        match *jump_status {
+            JumpStatus::OnGround if !grounded => *jump_status = JumpStatus::InAir { jumps: 0 },
+            JumpStatus::InAir { .. } if grounded => *jump_status = JumpStatus::OnGround,
+            JumpStatus::OnGround | JumpStatus::InAir { .. } | JumpStatus::Swimming => {}
+        }
+    }
+}
+
+#[instrument(skip(commands, state, query))]
+pub fn update_sleeping(
+    mut commands: Commands,
+    state: Res<PhysicsState>,
+    mut query: Query<
+        (
+            Entity,
+            &EntityWorldRect,
+            &mut Velocity,
+            &mut IdleSteps,
+            Option<&mut PreviousBounds>,
+            &BodyType,
+            Option<&StandingOn>,
+        ),
+        Without<Sleeping>,
+    >,
+) {
+    for (entity, bounds, mut velocity, mut idle_steps, previous_bounds, &body_type, standing_on) in
+        query.iter_mut()
+    {
+        // Bodies standing on moving bodies need to stay awake to be carried
+        let carried = standing_on.and_then(|standing_on| standing_on.0).is_some();
+        if body_type != BodyType::Kinematic || carried {
+            continue;
+        }
+
+        // Reset the idle counter if the body is moving
+        if velocity.0.x.hypot(velocity.0.y) >= state.sleep_velocity {
+            idle_steps.0 = 0;
+            continue;
+        }
+
+        idle_steps.0 += 1;
+        if idle_steps.0 >= state.sleep_steps {
+            // Bring the body to a complete stop so that any velocity applied
+            // to it later wakes it up
+            velocity.0 = EntityWorldPosition::ZERO;
+            if let Some(mut previous_bounds) = previous_bounds {
+                previous_bounds.0 = Some(*bounds);
             }
+
+            commands.entity(entity).insert(Sleeping);
+        }
+    }
+}
+
+#[instrument(skip(commands, tile_changes, awake, sleeping))]
+pub fn wake_bodies(
+    mut commands: Commands,
+    mut tile_changes: EventReader<TileChanged>,
+    awake: Query<(&EntityWorldRect, &BodyType), Without<Sleeping>>,
+    mut sleeping: Query<
+        (
+            Entity,
+            &EntityWorldRect,
+            &Velocity,
+            &Forces,
+            Option<&MoveDirection>,
+            &mut IdleSteps,
+        ),
+        With<Sleeping>,
+    >,
+) {
+    // Get the areas around any tiles that changed
+    let changed_areas: Vec<_> = tile_changes
+        .iter()
+        .map(|change| {
+            let position =
+                EntityWorldPosition::new(change.position.x as f32, change.position.y as f32);
+            EntityWorldRect::new(position, EntityWorldPosition::ONE).expand(1.0)
+        })
+        .collect();
+
+    // Get the bounds of all moving bodies
+    let moving: Vec<_> = awake
+        .iter()
+        .filter(|&(_, &body_type)| body_type != BodyType::Static)
+        .map(|(&bounds, _)| bounds.expand(CONTACT_EPSILON))
+        .collect();
+
+    for (entity, &bounds, velocity, forces, direction, mut idle_steps) in sleeping.iter_mut() {
+        let wake = velocity.0 != EntityWorldPosition::ZERO
+            || !forces.0.is_empty()
+            || direction.map_or(false, |direction| direction.0 != 0.0)
+            || changed_areas.iter().any(|&area| area.intersects(bounds))
+            || moving.iter().any(|&other| other.intersects(bounds));
+
+        if wake {
+            idle_steps.0 = 0;
+            commands.entity(entity).remove::<Sleeping>();
         }
     }
 }
 
+#[instrument(skip(body_tree, changed, removed))]
+pub fn update_body_tree(
+    mut body_tree: ResMut<BodyTree>,
+    changed: Query<(Entity, &EntityWorldRect), (With<BodyType>, Changed<EntityWorldRect>)>,
+    removed: RemovedComponents<BodyType>,
+) {
+    for entity in removed.iter() {
+        body_tree.remove(entity);
+    }
+
+    for (entity, &bounds) in changed.iter() {
+        body_tree.update(entity, bounds);
+    }
+}
+
 #[instrument(skip(query))]
 pub fn cleanup_kinematics(mut query: Query<&mut Acceleration>) {
     for mut acceleration in query.iter_mut() {
@@ -325,25 +710,21 @@ pub fn cleanup_kinematics(mut query: Query<&mut Acceleration>) {
     }
 }
 
-// #[instrument(skip(state, query))]
-// pub fn update_transforms(
-//     state: Res<PhysicsState>,
-//     mut query: Query<(&mut Transform, &EntityWorldRect, &Velocity)>,
-// ) {
-//     for (mut transform, bounds, velocity) in query.iter_mut() {
-//         let center = Vec2::from(bounds.center()).extend(transform.translation[2]);
-//         let predicted_offset = Vec2::from(velocity.0).extend(0.0)
-//             * state.step_timer.percent()
-//             * state.step_timer.duration().as_secs_f32();
-//         transform.translation = center + predicted_offset;
-//         transform.translation;
-//     }
-// }
-
-#[instrument(skip(query))]
-pub fn update_transforms(mut query: Query<(&mut Transform, &EntityWorldRect)>) {
-    for (mut transform, bounds) in query.iter_mut() {
-        transform.translation = Vec2::from(bounds.center()).extend(transform.translation[2]);
-        transform.translation;
+#[instrument(skip(state, query))]
+pub fn update_transforms(
+    state: Res<PhysicsState>,
+    mut query: Query<(&mut Transform, &EntityWorldRect, Option<&PreviousBounds>)>,
+) {
+    // Interpolate between the last two steps by the time left in the
+    // accumulator
+    let alpha = state.step_timer.percent();
+    for (mut transform, bounds, previous_bounds) in query.iter_mut() {
+        let current = Vec2::from(bounds.center());
+        let center = match previous_bounds.and_then(|previous_bounds| previous_bounds.0) {
+            Some(previous_bounds) => Vec2::from(previous_bounds.center()).lerp(current, alpha),
+            None => current,
+        };
+
+        transform.translation = center.extend(transform.translation[2]);
     }
 }
@@ -0,0 +1,40 @@
+//! Helpers shared by the unit tests in this crate.
+
+use game_tiles::{
+    generation::FlatWorldGenerator, EntityWorldPosition, EntityWorldRect, GameWorld,
+    RegionWorldPosition, Tile, TileWorldCoordinate, TileWorldPosition,
+};
+
+/// Creates a world with a floor of stone along `y = 0` and the given tiles
+/// placed on top of it.
+pub fn flat_world(tiles: &[(Tile, TileWorldCoordinate, TileWorldCoordinate)]) -> GameWorld {
+    let generator = FlatWorldGenerator::new(Tile::Stone, Some(1));
+    let mut world = GameWorld::new(Box::new(generator));
+    for x in -2..2 {
+        for y in -2..2 {
+            world.get_or_generate_region(RegionWorldPosition::new(x, y));
+        }
+    }
+
+    for &(tile, x, y) in tiles {
+        *world.get_or_generate_tile(TileWorldPosition::new(x, y)) = Some(tile);
+    }
+
+    world
+}
+
+pub fn rect(x: f32, y: f32, width: f32, height: f32) -> EntityWorldRect {
+    EntityWorldRect::new(
+        EntityWorldPosition::new(x, y),
+        EntityWorldPosition::new(width, height),
+    )
+}
+
+pub fn assert_close(expected: f32, actual: f32) {
+    assert!(
+        (expected - actual).abs() < 0.0001,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
@@ -0,0 +1,129 @@
+use crate::{Fluid, PhysicsMaterial};
+use game_lib::bevy::prelude::*;
+use game_tiles::{EntityWorldRect, Tile, TileWorldPosition};
+
+/// The shape of a tile used when calculating collisions against it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Reflect)]
+pub enum TileCollisionShape {
+    /// Bodies pass through the tile freely.
+    None,
+
+    /// The tile fills its entire cell.
+    Full,
+
+    /// The tile fills the bottom half of its cell.
+    HalfSlab,
+
+    /// The top of the tile can be landed on, but bodies can jump up through
+    /// it, move through it horizontally, and drop through it.
+    OneWay,
+
+    /// A 45° slope rising from the bottom of the cell to its top towards the
+    /// given side.
+    Slope(SlopeDirection),
+
+    /// The lower half of a shallow slope, rising from the bottom of the cell
+    /// to its middle towards the given side. Shallow slopes span two tiles,
+    /// which is the closest a tile grid gets to a 22.5° slope.
+    ShallowSlopeLower(SlopeDirection),
+
+    /// The upper half of a shallow slope, rising from the middle of the cell
+    /// to its top towards the given side.
+    ShallowSlopeUpper(SlopeDirection),
+}
+
+/// The side a slope rises towards.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Reflect)]
+pub enum SlopeDirection {
+    Left,
+    Right,
+}
+
+impl TileCollisionShape {
+    /// Gets the highest point of the tile's surface over the given horizontal
+    /// span, relative to the bottom of the tile. Both ends of the span are in
+    /// the range `[0, 1]`, relative to the left of the tile.
+    pub fn surface_height(self, left: f32, right: f32) -> Option<f32> {
+        // Height of a 45° slope rising towards the given side
+        let rise = |direction: SlopeDirection| match direction {
+            SlopeDirection::Left => 1.0 - left,
+            SlopeDirection::Right => right,
+        };
+
+        match self {
+            TileCollisionShape::None => None,
+            TileCollisionShape::Full | TileCollisionShape::OneWay => Some(1.0),
+            TileCollisionShape::HalfSlab => Some(0.5),
+            TileCollisionShape::Slope(direction) => Some(rise(direction)),
+            TileCollisionShape::ShallowSlopeLower(direction) => Some(rise(direction) / 2.0),
+            TileCollisionShape::ShallowSlopeUpper(direction) => Some(0.5 + rise(direction) / 2.0),
+        }
+    }
+
+    /// Gets the y-coordinate of the top of a tile with this shape at the given
+    /// position, only considering the part of the tile horizontally covered
+    /// by `bounds`.
+    pub fn surface_top(self, position: TileWorldPosition, bounds: EntityWorldRect) -> Option<f32> {
+        let left = (bounds.left() - position.x as f32).clamp(0.0, 1.0);
+        let right = (bounds.right() - position.x as f32).clamp(0.0, 1.0);
+        self.surface_height(left, right)
+            .map(|height| position.y as f32 + height)
+    }
+}
+
+/// Physics properties of tiles.
+pub trait TilePhysicsExt {
+    fn collision_shape(self) -> TileCollisionShape;
+    fn material(self) -> PhysicsMaterial;
+    fn fluid(self) -> Option<Fluid>;
+}
+
+impl TilePhysicsExt for Tile {
+    fn collision_shape(self) -> TileCollisionShape {
+        match self {
+            Tile::Stone | Tile::Dirt | Tile::Ice | Tile::Slime => TileCollisionShape::Full,
+            Tile::StoneSlab => TileCollisionShape::HalfSlab,
+            Tile::WoodPlatform => TileCollisionShape::OneWay,
+            Tile::Water => TileCollisionShape::None,
+            Tile::StoneSlopeLeft => TileCollisionShape::Slope(SlopeDirection::Left),
+            Tile::StoneSlopeRight => TileCollisionShape::Slope(SlopeDirection::Right),
+            Tile::StoneShallowSlopeLeftLower => {
+                TileCollisionShape::ShallowSlopeLower(SlopeDirection::Left)
+            }
+            Tile::StoneShallowSlopeLeftUpper => {
+                TileCollisionShape::ShallowSlopeUpper(SlopeDirection::Left)
+            }
+            Tile::StoneShallowSlopeRightLower => {
+                TileCollisionShape::ShallowSlopeLower(SlopeDirection::Right)
+            }
+            Tile::StoneShallowSlopeRightUpper => {
+                TileCollisionShape::ShallowSlopeUpper(SlopeDirection::Right)
+            }
+        }
+    }
+
+    fn material(self) -> PhysicsMaterial {
+        match self {
+            Tile::Ice => PhysicsMaterial::new(0.01, 0.0),
+            Tile::Slime => PhysicsMaterial::new(1.0, 0.8),
+            Tile::WoodPlatform => PhysicsMaterial::new(0.6, 0.0),
+            Tile::Dirt => PhysicsMaterial::new(0.7, 0.0),
+            Tile::Stone
+            | Tile::StoneSlab
+            | Tile::StoneSlopeLeft
+            | Tile::StoneSlopeRight
+            | Tile::StoneShallowSlopeLeftLower
+            | Tile::StoneShallowSlopeLeftUpper
+            | Tile::StoneShallowSlopeRightLower
+            | Tile::StoneShallowSlopeRightUpper => PhysicsMaterial::new(0.8, 0.0),
+            Tile::Water => PhysicsMaterial::default(),
+        }
+    }
+
+    fn fluid(self) -> Option<Fluid> {
+        match self {
+            Tile::Water => Some(Fluid::WATER),
+            _ => None,
+        }
+    }
+}
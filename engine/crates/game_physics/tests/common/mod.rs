@@ -0,0 +1,199 @@
+//! Headless harness for simulating the physics engine in integration tests.
+
+// Not every test uses every part of the harness
+#![allow(dead_code)]
+
+use game_core::{modes::Mode, CorePlugin, GlobalMode};
+use game_lib::bevy::{app::ManualEventReader, prelude::*};
+use game_physics::{
+    Gravity, JumpStatus, PhysicsBundle, PhysicsPlugin, PhysicsState, TileCollision,
+    TileCollisionAxis, Velocity,
+};
+use game_tiles::{
+    generation::FlatWorldGenerator, EntityWorldPosition, EntityWorldRect, GameWorld,
+    RegionWorldPosition, Tile, TileChanged, TileWorldPosition,
+};
+
+/// Length of a single physics step in seconds.
+pub const STEP: f32 = 1.0 / 30.0;
+
+/// Builds a world from an ASCII-art tile map. The bottom row of the map is at
+/// `y = 0` and the leftmost column is at `x = 0`. Leading and trailing
+/// whitespace on each line is ignored, so maps can be indented.
+///
+/// - `.`: air
+/// - `#`: stone
+/// - `d`: dirt
+/// - `_`: stone slab
+/// - `=`: wood platform
+/// - `/`: stone slope rising to the right
+/// - `\`: stone slope rising to the left
+/// - `i`: ice
+/// - `s`: slime
+/// - `~`: water
+pub fn world_from_map(map: &str) -> GameWorld {
+    let rows: Vec<_> = map
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let mut world = GameWorld::new(Box::new(FlatWorldGenerator::new(Tile::Stone, Some(0))));
+    for (row, line) in rows.iter().rev().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let tile = match c {
+                '.' => None,
+                '#' => Some(Tile::Stone),
+                'd' => Some(Tile::Dirt),
+                '_' => Some(Tile::StoneSlab),
+                '=' => Some(Tile::WoodPlatform),
+                '/' => Some(Tile::StoneSlopeRight),
+                '\\' => Some(Tile::StoneSlopeLeft),
+                'i' => Some(Tile::Ice),
+                's' => Some(Tile::Slime),
+                '~' => Some(Tile::Water),
+                _ => panic!("unknown tile {:?} in map", c),
+            };
+
+            let position = TileWorldPosition::new(column as i32, row as i32);
+            *world.get_or_generate_tile(position) = tile;
+        }
+    }
+
+    // Generate the regions around the map so bodies can leave it
+    let width = rows.iter().map(|line| line.len()).max().unwrap_or(0) as i32;
+    let top_right = RegionWorldPosition::from(TileWorldPosition::new(width, rows.len() as i32));
+    for x in -1..=top_right.x + 1 {
+        for y in -1..=top_right.y + 1 {
+            world.get_or_generate_region(RegionWorldPosition::new(x, y));
+        }
+    }
+
+    world
+}
+
+/// A headless app running the physics engine one fixed step at a time.
+pub struct PhysicsHarness {
+    app: App,
+    collisions: Vec<TileCollision>,
+    reader: ManualEventReader<TileCollision>,
+}
+
+impl PhysicsHarness {
+    /// Creates an app with the physics engine simulating the given map.
+    pub fn new(map: &str) -> Self {
+        let mut builder = App::build();
+        builder
+            .add_plugins(MinimalPlugins)
+            .add_plugin(CorePlugin)
+            .add_plugin(PhysicsPlugin)
+            .add_event::<TileChanged>()
+            .insert_resource(Mode::new(GlobalMode::InGame))
+            .insert_resource(world_from_map(map))
+            .insert_resource(PhysicsState {
+                gravity: Gravity(EntityWorldPosition::Y * -9.81),
+                deterministic: true,
+                ..Default::default()
+            });
+
+        let mut harness = PhysicsHarness {
+            app: builder.app,
+            collisions: Vec::new(),
+            reader: Default::default(),
+        };
+
+        // Steps are only taken when requested, never from the frame time
+        harness.state_mut().step_timer.pause();
+        harness.app.update();
+        harness
+    }
+
+    /// Spawns a kinematic body with the given bottom left corner and size.
+    pub fn spawn_body(&mut self, x: f32, y: f32, width: f32, height: f32) -> Entity {
+        self.app
+            .world
+            .spawn()
+            .insert_bundle(PhysicsBundle {
+                bounds: EntityWorldRect::new(
+                    EntityWorldPosition::new(x, y),
+                    EntityWorldPosition::new(width, height),
+                ),
+                ..Default::default()
+            })
+            .id()
+    }
+
+    /// Adds a component to an entity, for example to give a body its own
+    /// drag or jump status.
+    pub fn insert<C: Component>(&mut self, entity: Entity, component: C) {
+        self.app.world.entity_mut(entity).insert(component);
+    }
+
+    /// Simulates `steps` fixed physics steps.
+    pub fn step(&mut self, steps: u32) {
+        for _ in 0..steps {
+            self.state_mut().queued_steps = 1;
+            self.app.update();
+
+            let events = self
+                .app
+                .world
+                .get_resource::<Events<TileCollision>>()
+                .unwrap();
+            self.collisions.extend(self.reader.iter(events).cloned());
+        }
+    }
+
+    pub fn state_mut(&mut self) -> Mut<'_, PhysicsState> {
+        self.app.world.get_resource_mut().unwrap()
+    }
+
+    pub fn bounds(&self, entity: Entity) -> EntityWorldRect {
+        *self.app.world.get::<EntityWorldRect>(entity).unwrap()
+    }
+
+    pub fn velocity(&self, entity: Entity) -> EntityWorldPosition {
+        self.app.world.get::<Velocity>(entity).unwrap().0
+    }
+
+    pub fn set_velocity(&mut self, entity: Entity, velocity: EntityWorldPosition) {
+        self.app.world.get_mut::<Velocity>(entity).unwrap().0 = velocity;
+    }
+
+    pub fn jump_status(&self, entity: Entity) -> JumpStatus {
+        *self.app.world.get::<JumpStatus>(entity).unwrap()
+    }
+
+    /// Every tile collision sent since the harness was created.
+    pub fn collisions(&self) -> &[TileCollision] {
+        &self.collisions
+    }
+
+    /// Asserts that the bottom left corner of a body is at the given position.
+    pub fn assert_position(&self, entity: Entity, x: f32, y: f32) {
+        let position = self.bounds(entity).bottom_left;
+        assert!(
+            (position.x - x).abs() < 0.001 && (position.y - y).abs() < 0.001,
+            "expected body at ({}, {}), but it was at {}",
+            x,
+            y,
+            position
+        );
+    }
+
+    /// Asserts that a body collided with the given tile along an axis.
+    pub fn assert_collided(&self, entity: Entity, axis: TileCollisionAxis, x: i32, y: i32) {
+        let position = TileWorldPosition::new(x, y);
+        assert!(
+            self.collisions
+                .iter()
+                .any(|collision| collision.entity == entity
+                    && collision.axis == axis
+                    && collision.tile_position == position),
+            "expected a collision along {:?} with the tile at {}, but got {:?}",
+            axis,
+            position,
+            self.collisions
+        );
+    }
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use common::{PhysicsHarness, STEP};
+use game_physics::{Drag, JumpStatus, MoveDirection, MovementConfig, TileCollisionAxis};
+use game_tiles::{EntityWorldPosition, TileWorldPosition};
+
+#[test]
+fn body_lands_on_floor() {
+    let mut harness = PhysicsHarness::new(
+        "
+        ........
+        ........
+        ........
+        ........
+        ########
+        ",
+    );
+    let body = harness.spawn_body(2.25, 3.0, 0.5, 0.5);
+    harness.step(60);
+
+    harness.assert_position(body, 2.25, 1.0);
+    harness.assert_collided(body, TileCollisionAxis::Y, 2, 0);
+    assert_eq!(0.0, harness.velocity(body).y);
+}
+
+#[test]
+fn body_stops_at_wall() {
+    let mut harness = PhysicsHarness::new(
+        "
+        ......#
+        ......#
+        #######
+        ",
+    );
+    let body = harness.spawn_body(1.0, 1.0, 0.5, 0.5);
+    harness.set_velocity(body, EntityWorldPosition::new(20.0, 0.0));
+    harness.step(30);
+
+    harness.assert_position(body, 5.5, 1.0);
+    harness.assert_collided(body, TileCollisionAxis::X, 6, 1);
+    assert_eq!(0.0, harness.velocity(body).x);
+}
+
+#[test]
+fn landing_resets_jumps() {
+    let mut harness = PhysicsHarness::new(
+        "
+        ....
+        ....
+        ####
+        ",
+    );
+    let body = harness.spawn_body(1.25, 1.0, 0.5, 0.5);
+    harness.insert(body, JumpStatus::InAir { jumps: 1 });
+    harness.set_velocity(body, EntityWorldPosition::new(0.0, 5.0));
+
+    harness.step(5);
+    assert_eq!(JumpStatus::InAir { jumps: 1 }, harness.jump_status(body));
+
+    harness.step(60);
+    assert_eq!(JumpStatus::OnGround, harness.jump_status(body));
+    harness.assert_position(body, 1.25, 1.0);
+}
+
+#[test]
+fn falling_body_reaches_terminal_velocity() {
+    let mut harness = PhysicsHarness::new("#");
+    let body = harness.spawn_body(0.0, 1000.0, 0.5, 0.5);
+    let terminal_velocity = 10.0;
+    harness.insert(
+        body,
+        Drag::from_terminal_velocity(terminal_velocity, 62.0, 9.81),
+    );
+
+    // Fall for 10 seconds
+    harness.step((10.0 / STEP) as u32);
+    let velocity = harness.velocity(body).y;
+    assert!(
+        (velocity + terminal_velocity).abs() < 0.1,
+        "expected terminal velocity of {}, got {}",
+        -terminal_velocity,
+        velocity
+    );
+}
+
+#[test]
+fn movement_accelerates_and_decelerates() {
+    let mut harness = PhysicsHarness::new(
+        "
+        ..................................
+        ..................................
+        ##################################
+        ",
+    );
+    let body = harness.spawn_body(1.0, 1.0, 0.5, 0.5);
+    let config = MovementConfig::default();
+    harness.insert(body, config);
+    harness.insert(body, MoveDirection(1.0));
+
+    // Speeds up gradually instead of snapping to the max speed
+    harness.step(1);
+    let velocity = harness.velocity(body).x;
+    assert!(
+        velocity > 0.0 && velocity < config.max_speed,
+        "{}",
+        velocity
+    );
+
+    harness.step(30);
+    let velocity = harness.velocity(body).x;
+    assert!(velocity > config.max_speed * 0.8, "{}", velocity);
+    assert!(velocity <= config.max_speed, "{}", velocity);
+
+    // Stops once no direction is held
+    harness.insert(body, MoveDirection(0.0));
+    harness.step(30);
+    let velocity = harness.velocity(body).x;
+    assert!(velocity.abs() < 0.01, "{}", velocity);
+}
+
+/// Gets how far a body slides after running at full speed over a floor made
+/// of the given tile and then letting go.
+fn stopping_distance(floor: char) -> f32 {
+    let air = ".".repeat(40);
+    let map = format!("{}\n{}\n{}", air, air, floor.to_string().repeat(40));
+    let mut harness = PhysicsHarness::new(&map);
+    let body = harness.spawn_body(1.0, 1.0, 0.5, 0.5);
+    harness.insert(body, MovementConfig::default());
+    harness.insert(body, MoveDirection(1.0));
+    harness.step(30);
+
+    harness.insert(body, MoveDirection(0.0));
+    let start = harness.bounds(body).left();
+    harness.step(90);
+    let velocity = harness.velocity(body).x;
+    assert!(velocity.abs() < 0.01, "{}", velocity);
+    harness.bounds(body).left() - start
+}
+
+#[test]
+fn bodies_slide_further_on_ice_than_stone() {
+    let stone = stopping_distance('#');
+    let ice = stopping_distance('i');
+    assert!(ice > stone * 2.0, "stone: {}, ice: {}", stone, ice);
+}
+
+/// Inputs given to a single body before a step.
+#[derive(Clone, Copy, Debug)]
+struct BodyInput {
+    direction: f32,
+    jump: bool,
+}
+
+/// Everything observable about a run, as raw bits so that replays must match
+/// exactly rather than approximately.
+#[derive(PartialEq, Debug)]
+struct RunTrace {
+    /// Bounds and velocity of every body after each step.
+    states: Vec<Vec<[u32; 6]>>,
+    /// Every tile collision in the order it was sent, identified by the index
+    /// of the body that collided.
+    collisions: Vec<(usize, TileCollisionAxis, TileWorldPosition, [u32; 2])>,
+}
+
+const REPLAY_MAP: &str = "
+    ........................
+    ........................
+    ........................
+    ........#.....=====.....
+    ........#...............
+    ......../..........\\....
+    ###_####d##iiiiisss#####
+    ";
+
+/// Runs a simulation with several bodies, feeding each body the inputs for
+/// every step.
+fn run_with_inputs(inputs: &[Vec<BodyInput>]) -> RunTrace {
+    let mut harness = PhysicsHarness::new(REPLAY_MAP);
+    let bodies: Vec<_> = [(1.0, 1.0), (5.25, 3.0), (12.5, 1.0), (20.0, 4.0)]
+        .iter()
+        .map(|&(x, y)| {
+            let body = harness.spawn_body(x, y, 0.75, 1.5);
+            harness.insert(body, MovementConfig::default());
+            body
+        })
+        .collect();
+
+    let mut states = Vec::with_capacity(inputs.len());
+    for step in inputs {
+        for (&body, input) in bodies.iter().zip(step) {
+            harness.insert(body, MoveDirection(input.direction));
+            if input.jump {
+                let velocity = harness.velocity(body);
+                harness.set_velocity(body, EntityWorldPosition::new(velocity.x, 6.0));
+            }
+        }
+
+        harness.step(1);
+        let state = bodies
+            .iter()
+            .map(|&body| {
+                let bounds = harness.bounds(body);
+                let velocity = harness.velocity(body);
+                [
+                    bounds.bottom_left.x.to_bits(),
+                    bounds.bottom_left.y.to_bits(),
+                    bounds.size.x.to_bits(),
+                    bounds.size.y.to_bits(),
+                    velocity.x.to_bits(),
+                    velocity.y.to_bits(),
+                ]
+            })
+            .collect();
+        states.push(state);
+    }
+
+    let collisions = harness
+        .collisions()
+        .iter()
+        .map(|collision| {
+            let body = bodies
+                .iter()
+                .position(|&body| body == collision.entity)
+                .unwrap();
+            let velocity = collision.entity_velocity;
+            (
+                body,
+                collision.axis,
+                collision.tile_position,
+                [velocity.x.to_bits(), velocity.y.to_bits()],
+            )
+        })
+        .collect();
+
+    RunTrace { states, collisions }
+}
+
+#[test]
+fn replaying_inputs_is_bit_exact() {
+    // Record a varied but repeatable set of inputs
+    let mut seed = 0x2545_f491_u32;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+    let inputs: Vec<Vec<_>> = (0..240)
+        .map(|_| {
+            (0..4)
+                .map(|_| BodyInput {
+                    direction: [-1.0, 0.0, 0.5, 1.0][(next() % 4) as usize],
+                    jump: next() % 20 == 0,
+                })
+                .collect()
+        })
+        .collect();
+
+    let recorded = run_with_inputs(&inputs);
+    assert!(!recorded.collisions.is_empty());
+
+    let replayed = run_with_inputs(&inputs);
+    assert_eq!(recorded, replayed);
+}
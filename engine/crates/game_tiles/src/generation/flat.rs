@@ -42,4 +42,8 @@ impl WorldGenerator for FlatWorldGenerator {
             }
         }
     }
+
+    fn surface_height(&self, _x: TileWorldCoordinate) -> Option<TileWorldCoordinate> {
+        self.fill_height
+    }
 }
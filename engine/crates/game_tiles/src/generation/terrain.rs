@@ -1,6 +1,6 @@
 use crate::{
     generation::{Waves, WavesConfig, WorldGenerator},
-    Region, RegionWorldPosition, Tile, TileWorldPosition,
+    Region, RegionWorldPosition, Tile, TileWorldCoordinate, TileWorldPosition,
 };
 use game_lib::rand::Rng;
 use std::convert::TryInto;
@@ -48,11 +48,17 @@ impl TerrainWorldGenerator {
     }
 }
 
+impl TerrainWorldGenerator {
+    fn terrain_height(&self, x: TileWorldCoordinate) -> TileWorldCoordinate {
+        (100.0 + self.terrain_waves.get(x as f32)) as TileWorldCoordinate
+    }
+}
+
 impl WorldGenerator for TerrainWorldGenerator {
     fn populate_region(&mut self, region_position: RegionWorldPosition, region: &mut Region) {
         let world_position: TileWorldPosition = region_position.into();
         for x in (0..Region::WIDTH.into()).map(|x| x + world_position.x) {
-            let terrain_height = (100.0 + self.terrain_waves.get(x as f32)) as i32;
+            let terrain_height = self.terrain_height(x);
             let dirt_height = (10.0 + self.dirt_waves.get(x as f32)).max(0.0) as i32;
 
             // Stone
@@ -76,4 +82,8 @@ impl WorldGenerator for TerrainWorldGenerator {
             }
         }
     }
+
+    fn surface_height(&self, x: TileWorldCoordinate) -> Option<TileWorldCoordinate> {
+        Some(self.terrain_height(x))
+    }
 }
@@ -1,7 +1,14 @@
-use crate::{Region, RegionWorldPosition};
+use crate::{Region, RegionWorldPosition, TileWorldCoordinate};
 use game_lib::bevy::ecs::component::Component;
 use std::fmt::Debug;
 
 pub trait WorldGenerator: Component + Debug {
     fn populate_region(&mut self, region_position: RegionWorldPosition, region: &mut Region);
+
+    /// Gets the y coordinate of the lowest empty tile above the generated
+    /// surface in the given column, if the generator has a surface. This is
+    /// only a hint, since tiles may have changed after being generated.
+    fn surface_height(&self, _x: TileWorldCoordinate) -> Option<TileWorldCoordinate> {
+        None
+    }
 }
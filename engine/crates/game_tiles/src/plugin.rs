@@ -1,6 +1,6 @@
 use crate::{
     render::RegionData, systems::WorldRedrawEvent, RegionWorldPosition, RegionWorldRect, Tile,
-    TileRegionPosition, TileRegionRect, TileWorldPosition, TileWorldRect,
+    TileChanged, TileRegionPosition, TileRegionRect, TileWorldPosition, TileWorldRect,
 };
 use game_camera::CameraPlugin;
 use game_core::{loading::MainLoadingMode, modes::ModeExt, GameStage, GlobalMode, ModeEvent};
@@ -22,10 +22,12 @@ impl Plugin for TilePlugin {
             .register_type::<RegionWorldRect>()
             // Events/components
             .register_type::<WorldRedrawEvent>()
+            .register_type::<TileChanged>()
             // .register_type::<Region>()
             // .register_type::<GameWorld>()
             .add_asset::<RegionData>()
             .add_event::<WorldRedrawEvent>()
+            .add_event::<TileChanged>()
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
                 SystemSet::new()
@@ -56,6 +58,15 @@ impl Plugin for TilePlugin {
                     .with_system(crate::systems::world_changed.system())
                     .with_system(crate::systems::camera_changed.system()),
             )
+            .add_system_set_to_stage(
+                GameStage::GameUpdate,
+                SystemSet::new()
+                    .label(TilePlugin)
+                    .label(TileSystem::DetectChanges)
+                    .after(TileSystem::SetupWorld)
+                    .with_run_criteria(GlobalMode::InGame.on(ModeEvent::Active))
+                    .with_system(crate::systems::send_tile_changes.system()),
+            )
             .add_system_set_to_stage(
                 GameStage::GameUpdate,
                 SystemSet::new()
@@ -73,5 +84,6 @@ pub enum TileSystem {
     SetupRendering,
     SetupWorld,
     DetectRedraw,
+    DetectChanges,
     Redraw,
 }
@@ -1,8 +1,8 @@
 use crate::{
     generation::TerrainWorldGenerator,
     render::{add_region_render_graph, RegionBundle, RegionData},
-    GameWorld, GameWorldGetError, RegionWorldPosition, RegionWorldRect, TileWorldPosition,
-    TileWorldRect,
+    GameWorld, GameWorldGetError, RegionWorldPosition, RegionWorldRect, TileChanged,
+    TileWorldPosition, TileWorldRect,
 };
 use game_camera::{ProjectionExt, ScaledOrthographicProjection};
 use game_core::loading::RequiredAssetLoader;
@@ -56,6 +56,11 @@ pub fn world_changed(mut redraw_event: EventWriter<WorldRedrawEvent>, world: Res
     }
 }
 
+#[instrument(skip(tile_changed, world))]
+pub fn send_tile_changes(mut tile_changed: EventWriter<TileChanged>, world: Res<GameWorld>) {
+    tile_changed.send_batch(world.take_changes());
+}
+
 #[instrument(skip(redraw_event, query))]
 pub fn camera_changed(
     mut redraw_event: EventWriter<WorldRedrawEvent>,
@@ -9,6 +9,17 @@ use game_lib::{
 pub enum Tile {
     Stone,
     Dirt,
+    StoneSlab,
+    WoodPlatform,
+    StoneSlopeLeft,
+    StoneSlopeRight,
+    StoneShallowSlopeLeftLower,
+    StoneShallowSlopeLeftUpper,
+    StoneShallowSlopeRightLower,
+    StoneShallowSlopeRightUpper,
+    Ice,
+    Slime,
+    Water,
 }
 
 impl Tile {
@@ -16,6 +27,35 @@ impl Tile {
         match self {
             Tile::Dirt => 0.into(),
             Tile::Stone => 1.into(),
+            Tile::StoneSlab => 3.into(),
+            Tile::WoodPlatform => 4.into(),
+            Tile::StoneSlopeLeft => 5.into(),
+            Tile::StoneSlopeRight => 6.into(),
+            Tile::StoneShallowSlopeLeftLower => 7.into(),
+            Tile::StoneShallowSlopeLeftUpper => 8.into(),
+            Tile::StoneShallowSlopeRightLower => 9.into(),
+            Tile::StoneShallowSlopeRightUpper => 10.into(),
+            Tile::Ice => 11.into(),
+            Tile::Slime => 12.into(),
+            Tile::Water => 13.into(),
+        }
+    }
+
+    /// Seconds it takes to mine this tile, or `None` if it can't be mined.
+    pub fn hardness(self) -> Option<f32> {
+        match self {
+            Tile::Dirt | Tile::Slime => Some(0.5),
+            Tile::WoodPlatform => Some(0.3),
+            Tile::Ice => Some(0.75),
+            Tile::Stone
+            | Tile::StoneSlab
+            | Tile::StoneSlopeLeft
+            | Tile::StoneSlopeRight
+            | Tile::StoneShallowSlopeLeftLower
+            | Tile::StoneShallowSlopeLeftUpper
+            | Tile::StoneShallowSlopeRightLower
+            | Tile::StoneShallowSlopeRightUpper => Some(1.5),
+            Tile::Water => None,
         }
     }
 }
@@ -1,8 +1,12 @@
 use std::convert::TryInto;
 
-use crate::{generation::WorldGenerator, Region, RegionWorldPosition, Tile, TileWorldPosition};
+use crate::{
+    generation::WorldGenerator, EntityWorldPosition, EntityWorldRect, Region, RegionWorldPosition,
+    Tile, TileWorldCoordinate, TileWorldPosition, TileWorldRect,
+};
 use game_lib::{
-    bevy::utils::HashMap,
+    bevy::{prelude::*, utils::HashMap},
+    crossbeam::channel::{self, Receiver, Sender},
     derive_more::{Display, Error},
     tracing::trace_span,
 };
@@ -11,6 +15,8 @@ use game_lib::{
 pub struct GameWorld {
     regions: HashMap<RegionWorldPosition, Region>,
     generator: Box<dyn WorldGenerator>,
+    changes: (Sender<TileChanged>, Receiver<TileChanged>),
+    spawn_point: Option<EntityWorldPosition>,
 }
 
 impl GameWorld {
@@ -18,7 +24,103 @@ impl GameWorld {
         GameWorld {
             regions: HashMap::default(),
             generator,
+            changes: channel::unbounded(),
+            spawn_point: None,
+        }
+    }
+
+    /// Maximum number of tiles above the surface to search for room to spawn.
+    const SPAWN_SEARCH_HEIGHT: TileWorldCoordinate = 256;
+
+    /// Maximum number of columns to either side of the spawn point to search
+    /// for room to spawn.
+    const SPAWN_SEARCH_WIDTH: TileWorldCoordinate = 64;
+
+    /// Checks whether an area has no tiles in it, generating the area if
+    /// needed.
+    pub fn is_clear(&mut self, bounds: EntityWorldRect) -> bool {
+        TileWorldRect::from(bounds)
+            .iter_positions()
+            .all(|position| self.get_or_generate_tile(position).is_none())
+    }
+
+    /// Finds the lowest clear area of the given size centered on column `x`,
+    /// starting from the surface if the world generator knows where it is.
+    /// Returns `None` if there is no room within [`Self::SPAWN_SEARCH_HEIGHT`]
+    /// tiles of the surface.
+    pub fn find_spawn(
+        &mut self,
+        x: TileWorldCoordinate,
+        size: EntityWorldPosition,
+    ) -> Option<EntityWorldRect> {
+        let surface = self.generator.surface_height(x).unwrap_or(0);
+        let left = x as f32 + 0.5 - size.x / 2.0;
+        (surface..surface + Self::SPAWN_SEARCH_HEIGHT)
+            .map(|y| EntityWorldRect::new(EntityWorldPosition::new(left, y as f32), size))
+            .find(|&bounds| self.is_clear(bounds))
+    }
+
+    /// Gets the bottom center of the area where entities spawn in this world,
+    /// if it has been chosen yet.
+    pub fn spawn_point(&self) -> Option<EntityWorldPosition> {
+        self.spawn_point
+    }
+
+    /// Gets a clear area of the given size at the world's spawn point. The
+    /// spawn point is chosen the first time this is called, and is moved if
+    /// tiles have since been placed there. If the column the spawn point is
+    /// in has no room, the nearest columns to either side are searched too.
+    /// Returns `None` if no room was found, in which case the spawn point is
+    /// left unchanged.
+    pub fn get_spawn_bounds(&mut self, size: EntityWorldPosition) -> Option<EntityWorldRect> {
+        let existing = self.spawn_point.map(|spawn_point| {
+            let bottom_left = spawn_point - EntityWorldPosition::new(size.x / 2.0, 0.0);
+            EntityWorldRect::new(bottom_left, size)
+        });
+        if let Some(bounds) = existing.filter(|&bounds| self.is_clear(bounds)) {
+            return Some(bounds);
         }
+
+        // Search columns in order of distance: x, x + 1, x - 1, x + 2, ...
+        let x = self.spawn_point.map_or(0, |spawn_point| {
+            spawn_point.x.floor() as TileWorldCoordinate
+        });
+        let columns = (0..=Self::SPAWN_SEARCH_WIDTH).flat_map(|offset| {
+            let left = Some(x - offset).filter(|_| offset > 0);
+            std::iter::once(x + offset).chain(left)
+        });
+        let bounds = columns.find_map(|column| self.find_spawn(column, size))?;
+        self.spawn_point = Some(EntityWorldPosition::new(bounds.center().x, bounds.bottom()));
+        Some(bounds)
+    }
+
+    /// Replaces the tile at the given position, returning the old tile. Unlike
+    /// modifying the tile through [`GameWorld::get_tile_mut`], this sends a
+    /// [`TileChanged`] event if the tile is different.
+    pub fn set_tile(
+        &mut self,
+        position: TileWorldPosition,
+        tile: Option<Tile>,
+    ) -> Result<Option<Tile>, GameWorldGetError> {
+        let old = std::mem::replace(self.get_tile_mut(position)?, tile);
+        if old != tile {
+            self.changes
+                .0
+                .send(TileChanged {
+                    position,
+                    old,
+                    new: tile,
+                })
+                .unwrap();
+        }
+
+        Ok(old)
+    }
+
+    /// Takes all the changes made through [`GameWorld::set_tile`] since the
+    /// last time this was called.
+    pub fn take_changes(&self) -> impl Iterator<Item = TileChanged> + '_ {
+        self.changes.1.try_iter()
     }
 
     pub fn get_tile(
@@ -83,8 +185,125 @@ impl GameWorld {
     }
 }
 
+/// A tile in the world was replaced.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Reflect)]
+pub struct TileChanged {
+    pub position: TileWorldPosition,
+    pub old: Option<Tile>,
+    pub new: Option<Tile>,
+}
+
 #[derive(Clone, Debug, Display, Error)]
 pub enum GameWorldGetError {
     #[display(fmt = "the requested item has not been generated yet")]
     NotYetGenerated,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generation::FlatWorldGenerator;
+
+    /// Size of the bodies spawned in these tests.
+    const SIZE: EntityWorldPosition = EntityWorldPosition::new(1.5, 2.5);
+
+    fn flat_world(fill_height: Option<TileWorldCoordinate>) -> GameWorld {
+        GameWorld::new(Box::new(FlatWorldGenerator::new(Tile::Stone, fill_height)))
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> EntityWorldRect {
+        EntityWorldRect::new(
+            EntityWorldPosition::new(x, y),
+            EntityWorldPosition::new(width, height),
+        )
+    }
+
+    #[test]
+    fn is_clear_checks_every_overlapped_tile() {
+        let mut world = flat_world(Some(4));
+        assert!(world.is_clear(rect(0.0, 4.0, 1.0, 2.0)));
+        assert!(!world.is_clear(rect(0.0, 3.5, 1.0, 2.0)));
+
+        *world.get_or_generate_tile(TileWorldPosition::new(2, 5)) = Some(Tile::Dirt);
+        assert!(world.is_clear(rect(0.5, 4.0, 1.5, 2.0)));
+        assert!(!world.is_clear(rect(0.5, 4.0, 1.6, 2.0)));
+    }
+
+    #[test]
+    fn find_spawn_starts_at_surface() {
+        let mut world = flat_world(Some(4));
+        let bounds = world.find_spawn(0, SIZE).unwrap();
+        assert_eq!(rect(0.5 - SIZE.x / 2.0, 4.0, SIZE.x, SIZE.y), bounds);
+    }
+
+    #[test]
+    fn find_spawn_climbs_over_tiles() {
+        let mut world = flat_world(Some(4));
+        *world.get_or_generate_tile(TileWorldPosition::new(1, 6)) = Some(Tile::Dirt);
+        let bounds = world.find_spawn(0, SIZE).unwrap();
+        assert_eq!(7.0, bounds.bottom());
+    }
+
+    #[test]
+    fn find_spawn_gives_up_above_search_height() {
+        let mut world = flat_world(Some(4));
+        for y in 4..4 + GameWorld::SPAWN_SEARCH_HEIGHT {
+            *world.get_or_generate_tile(TileWorldPosition::new(0, y)) = Some(Tile::Dirt);
+        }
+
+        assert_eq!(None, world.find_spawn(0, SIZE));
+    }
+
+    #[test]
+    fn spawn_searches_neighbouring_columns() {
+        let mut world = flat_world(Some(4));
+        for y in 4..4 + GameWorld::SPAWN_SEARCH_HEIGHT {
+            *world.get_or_generate_tile(TileWorldPosition::new(0, y)) = Some(Tile::Dirt);
+        }
+
+        // Columns -1 and 1 are too close to fit a body next to the blocked one
+        let bounds = world.get_spawn_bounds(SIZE).unwrap();
+        assert_eq!(rect(2.5 - SIZE.x / 2.0, 4.0, SIZE.x, SIZE.y), bounds);
+        assert_eq!(
+            Some(EntityWorldPosition::new(2.5, 4.0)),
+            world.spawn_point()
+        );
+    }
+
+    #[test]
+    fn no_spawn_without_room() {
+        let mut world = flat_world(None);
+        assert_eq!(None, world.get_spawn_bounds(SIZE));
+        assert_eq!(None, world.spawn_point());
+    }
+
+    #[test]
+    fn spawn_point_is_reused() {
+        let mut world = flat_world(Some(4));
+        assert_eq!(None, world.spawn_point());
+
+        let first = world.get_spawn_bounds(SIZE).unwrap();
+        assert_eq!(
+            Some(EntityWorldPosition::new(0.5, 4.0)),
+            world.spawn_point()
+        );
+        assert_eq!(Some(first), world.get_spawn_bounds(SIZE));
+    }
+
+    #[test]
+    fn spawn_point_moves_when_blocked() {
+        let mut world = flat_world(Some(4));
+        world.get_spawn_bounds(SIZE).unwrap();
+        world
+            .set_tile(TileWorldPosition::new(0, 4), Some(Tile::Dirt))
+            .unwrap();
+
+        let bounds = world.get_spawn_bounds(SIZE).unwrap();
+        assert_eq!(5.0, bounds.bottom());
+        assert!(world.is_clear(bounds));
+        assert_eq!(
+            Some(EntityWorldPosition::new(0.5, 5.0)),
+            world.spawn_point()
+        );
+    }
+}
@@ -224,6 +224,15 @@ macro_rules! pos_type {
                 self.bottom_left += offset;
                 self
             }
+
+            /// Whether the two rectangles overlap. Rectangles which only share
+            /// an edge are not considered to be overlapping.
+            pub fn intersects(self, other: $rect_name) -> bool {
+                self.left() < other.right()
+                    && self.right() > other.left()
+                    && self.bottom() < other.top()
+                    && self.top() > other.bottom()
+            }
         }
 
         $(pos_type!(@impl $impl, $pos_name, $rect_name, $coord_name, ($zero, $one));)*
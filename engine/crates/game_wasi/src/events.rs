@@ -0,0 +1,9 @@
+/// Sent when a module fails to load or traps while running. The module is
+/// disabled, but the remaining modules keep running.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ModError {
+    /// The id of the module, or its directory if its manifest couldn't be read.
+    pub module: String,
+    /// A description of what went wrong.
+    pub message: String,
+}
@@ -0,0 +1,279 @@
+//! Host functions imported by modules under the `game` namespace.
+//!
+//! Tiles are passed to modules as the ids given by [`tile_to_id`], and
+//! [`TILE_NONE`] for empty tiles. Functions which can fail return one of the
+//! negative error codes below instead.
+
+use game_lib::bevy::utils::HashSet;
+use game_tiles::{
+    GameWorld, GameWorldGetError, RegionWorldPosition, Tile, TileChanged, TileWorldPosition,
+};
+use std::{
+    ptr::NonNull,
+    sync::{Arc, Mutex},
+};
+use wasmer::{import_namespace, Exports, Function, Store, Val, WasmerEnv};
+
+/// The id of an empty tile.
+pub const TILE_NONE: i32 = -1;
+/// The tile's region hasn't been generated yet.
+pub const ERROR_NOT_GENERATED: i32 = -2;
+/// There is no world to access, for example because the game is in a menu.
+pub const ERROR_NO_WORLD: i32 = -3;
+/// The tile id passed by the module isn't valid.
+pub const ERROR_INVALID_TILE: i32 = -4;
+
+/// Returned by `region_state` if the region hasn't been generated yet.
+pub const REGION_NOT_GENERATED: i32 = 0;
+/// Returned by `region_state` if the region has been generated.
+pub const REGION_GENERATED: i32 = 1;
+
+/// Gets the id of a tile. Ids are part of the module ABI, so existing ids must
+/// never change. Keep these in sync with the discriminants of `Tile` in
+/// `modules/crates/mod_api/src/world.rs`.
+pub fn tile_to_id(tile: Option<Tile>) -> i32 {
+    match tile {
+        None => TILE_NONE,
+        Some(Tile::Stone) => 0,
+        Some(Tile::Dirt) => 1,
+        Some(Tile::StoneSlab) => 2,
+        Some(Tile::WoodPlatform) => 3,
+        Some(Tile::StoneSlopeLeft) => 4,
+        Some(Tile::StoneSlopeRight) => 5,
+        Some(Tile::StoneShallowSlopeLeftLower) => 6,
+        Some(Tile::StoneShallowSlopeLeftUpper) => 7,
+        Some(Tile::StoneShallowSlopeRightLower) => 8,
+        Some(Tile::StoneShallowSlopeRightUpper) => 9,
+        Some(Tile::Ice) => 10,
+        Some(Tile::Slime) => 11,
+        Some(Tile::Water) => 12,
+    }
+}
+
+/// Converts an id back into a tile, or `None` if the id isn't valid.
+pub fn tile_from_id(id: i32) -> Option<Option<Tile>> {
+    let tile = match id {
+        TILE_NONE => return Some(None),
+        0 => Tile::Stone,
+        1 => Tile::Dirt,
+        2 => Tile::StoneSlab,
+        3 => Tile::WoodPlatform,
+        4 => Tile::StoneSlopeLeft,
+        5 => Tile::StoneSlopeRight,
+        6 => Tile::StoneShallowSlopeLeftLower,
+        7 => Tile::StoneShallowSlopeLeftUpper,
+        8 => Tile::StoneShallowSlopeRightLower,
+        9 => Tile::StoneShallowSlopeRightUpper,
+        10 => Tile::Ice,
+        11 => Tile::Slime,
+        12 => Tile::Water,
+        _ => return None,
+    };
+
+    Some(Some(tile))
+}
+
+/// State shared between the runner and the host functions of every module.
+#[derive(Debug, Default)]
+pub(crate) struct HostState {
+    lent_world: Mutex<LentWorld>,
+    tile_subscribers: Mutex<HashSet<String>>,
+}
+
+#[derive(Debug, Default)]
+struct LentWorld {
+    world: Option<NonNull<GameWorld>>,
+    /// Edits made by modules, in order. These are applied to the world once
+    /// it is no longer lent.
+    edits: Vec<(TileWorldPosition, Option<Tile>)>,
+}
+
+// SAFETY: the world is only accessed through the pointer while it is lent,
+// and accesses are synchronized by the mutex around this.
+unsafe impl Send for LentWorld {}
+
+impl HostState {
+    /// Lets host functions read `world` while `f` runs.
+    pub fn lend_world<R>(&self, world: &GameWorld, f: impl FnOnce() -> R) -> R {
+        /// Stops lending the world even if `f` panics.
+        struct Loan<'a>(&'a HostState);
+
+        impl Drop for Loan<'_> {
+            fn drop(&mut self) {
+                let mut lent_world = self
+                    .0
+                    .lent_world
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner());
+                lent_world.world = None;
+            }
+        }
+
+        self.lent_world.lock().unwrap().world = Some(NonNull::from(world));
+        let _loan = Loan(self);
+        f()
+    }
+
+    /// Takes the edits made by modules since the last time this was called.
+    pub fn take_edits(&self) -> Vec<(TileWorldPosition, Option<Tile>)> {
+        std::mem::take(&mut self.lent_world.lock().unwrap().edits)
+    }
+
+    /// Calls `f` with the lent world and the pending edits, or returns `None`
+    /// if no world is lent.
+    fn with_world<R>(
+        &self,
+        f: impl FnOnce(&GameWorld, &mut Vec<(TileWorldPosition, Option<Tile>)>) -> R,
+    ) -> Option<R> {
+        let mut lent_world = self.lent_world.lock().unwrap();
+        let LentWorld { world, edits } = &mut *lent_world;
+
+        // SAFETY: the pointer is only set by `lend_world` while the world is
+        // borrowed, and is cleared before that borrow ends
+        let world = unsafe { world.as_ref()?.as_ref() };
+        Some(f(world, edits))
+    }
+
+    pub fn is_subscribed_to_tiles(&self, module: &str) -> bool {
+        self.tile_subscribers.lock().unwrap().contains(module)
+    }
+}
+
+/// The environment of a single module's host functions.
+#[derive(Clone, Debug)]
+pub(crate) struct HostEnv {
+    module: String,
+    state: Arc<HostState>,
+}
+
+impl WasmerEnv for HostEnv {}
+
+impl HostEnv {
+    pub fn new(module: String, state: Arc<HostState>) -> Self {
+        HostEnv { module, state }
+    }
+}
+
+/// Creates the `game` namespace for a module.
+pub(crate) fn game_namespace(store: &Store, env: HostEnv) -> Exports {
+    import_namespace! {
+        {
+            "get_tile" => Function::new_native_with_env(store, env.clone(), get_tile),
+            "set_tile" => Function::new_native_with_env(store, env.clone(), set_tile),
+            "region_state" => Function::new_native_with_env(store, env.clone(), region_state),
+            "subscribe_tile_changes" => Function::new_native_with_env(
+                store,
+                env.clone(),
+                subscribe_tile_changes,
+            ),
+            "unsubscribe_tile_changes" => Function::new_native_with_env(
+                store,
+                env,
+                unsubscribe_tile_changes,
+            ),
+        }
+    }
+}
+
+/// Parameters passed to `on_tile_changed`: `(x, y, old, new)`.
+pub(crate) fn tile_changed_params(change: &TileChanged) -> [Val; 4] {
+    [
+        Val::I32(change.position.x),
+        Val::I32(change.position.y),
+        Val::I32(tile_to_id(change.old)),
+        Val::I32(tile_to_id(change.new)),
+    ]
+}
+
+/// Gets a tile, including edits which haven't been applied yet.
+fn current_tile(
+    world: &GameWorld,
+    edits: &[(TileWorldPosition, Option<Tile>)],
+    position: TileWorldPosition,
+) -> Result<Option<Tile>, GameWorldGetError> {
+    match edits.iter().rev().find(|&&(edited, _)| edited == position) {
+        Some(&(_, tile)) => Ok(tile),
+        None => world.get_tile(position).map(|&tile| tile),
+    }
+}
+
+fn get_tile(env: &HostEnv, x: i32, y: i32) -> i32 {
+    let position = TileWorldPosition::new(x, y);
+    env.state
+        .with_world(|world, edits| match current_tile(world, edits, position) {
+            Ok(tile) => tile_to_id(tile),
+            Err(GameWorldGetError::NotYetGenerated) => ERROR_NOT_GENERATED,
+        })
+        .unwrap_or(ERROR_NO_WORLD)
+}
+
+/// Replaces a tile, returning the id of the old tile. The world is updated
+/// after the current hook returns, but `get_tile` sees the new tile right
+/// away.
+fn set_tile(env: &HostEnv, x: i32, y: i32, tile: i32) -> i32 {
+    let tile = match tile_from_id(tile) {
+        Some(tile) => tile,
+        None => return ERROR_INVALID_TILE,
+    };
+
+    let position = TileWorldPosition::new(x, y);
+    env.state
+        .with_world(|world, edits| match current_tile(world, edits, position) {
+            Ok(old) => {
+                edits.push((position, tile));
+                tile_to_id(old)
+            }
+            Err(GameWorldGetError::NotYetGenerated) => ERROR_NOT_GENERATED,
+        })
+        .unwrap_or(ERROR_NO_WORLD)
+}
+
+fn region_state(env: &HostEnv, x: i32, y: i32) -> i32 {
+    let position = RegionWorldPosition::new(x, y);
+    env.state
+        .with_world(|world, _| match world.get_region(position) {
+            Ok(_) => REGION_GENERATED,
+            Err(GameWorldGetError::NotYetGenerated) => REGION_NOT_GENERATED,
+        })
+        .unwrap_or(ERROR_NO_WORLD)
+}
+
+/// Makes the host call the module's `on_tile_changed(x, y, old, new)` export
+/// whenever a tile changes.
+fn subscribe_tile_changes(env: &HostEnv) {
+    env.state
+        .tile_subscribers
+        .lock()
+        .unwrap()
+        .insert(env.module.clone());
+}
+
+fn unsubscribe_tile_changes(env: &HostEnv) {
+    env.state
+        .tile_subscribers
+        .lock()
+        .unwrap()
+        .remove(&env.module);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One past the highest tile id.
+    const TILE_COUNT: i32 = 13;
+
+    #[test]
+    fn tile_ids_round_trip() {
+        for id in TILE_NONE..TILE_COUNT {
+            let tile = tile_from_id(id).expect("tile id is invalid");
+            assert_eq!(id, tile_to_id(tile));
+        }
+    }
+
+    #[test]
+    fn invalid_tile_ids() {
+        assert_eq!(None, tile_from_id(TILE_COUNT));
+        assert_eq!(None, tile_from_id(ERROR_INVALID_TILE));
+    }
+}
@@ -1,8 +1,11 @@
 pub(crate) mod systems;
 
+pub mod host;
 pub mod module;
 pub mod runner;
 
+mod events;
 mod plugin;
 
+pub use events::*;
 pub use plugin::*;
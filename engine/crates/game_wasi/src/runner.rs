@@ -1,3 +1,5 @@
+mod resolve;
 mod runner;
 
+pub use resolve::*;
 pub use runner::*;
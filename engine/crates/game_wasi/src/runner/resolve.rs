@@ -0,0 +1,323 @@
+use crate::runner::ModuleInfo;
+use game_lib::{
+    bevy::utils::HashSet,
+    derive_more::{Display, Error},
+};
+use semver::{Version, VersionReq};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+/// The modules which can be loaded, and the modules which can't.
+#[derive(Clone, Debug, Default)]
+pub struct LoadOrder<'a> {
+    /// Modules which can be loaded, in the order they should be loaded.
+    pub modules: Vec<&'a ModuleInfo>,
+    /// Modules which can't be loaded, and why.
+    pub failed: Vec<(&'a ModuleInfo, ResolveError)>,
+}
+
+/// Sorts modules so that each module is loaded after all of its
+/// dependencies. Optional dependencies which aren't present are ignored, but
+/// present dependencies must always match the required versions. Modules with
+/// no ordering constraints between them are sorted by id.
+///
+/// Modules with unmet dependencies or which are part of a dependency cycle
+/// can't be loaded, and neither can any modules which depend on them.
+pub fn resolve_load_order<'a>(modules: impl IntoIterator<Item = &'a ModuleInfo>) -> LoadOrder<'a> {
+    let modules: BTreeMap<&str, &ModuleInfo> = modules
+        .into_iter()
+        .map(|info| (info.manifest.id.as_str(), info))
+        .collect();
+    let mut load_order = LoadOrder::default();
+    let mut failed = HashSet::default();
+
+    // Check that each dependency is present and has a compatible version
+    let mut dependencies: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (&id, &info) in modules.iter() {
+        let mut resolved = Vec::new();
+        let mut unmet = Vec::new();
+        for dependency in info.manifest.dependencies.iter() {
+            match modules.get(dependency.id.as_str()) {
+                Some(found) if dependency.versions.matches(&found.manifest.version) => {
+                    resolved.push(found.manifest.id.as_str());
+                }
+                None if dependency.optional => {}
+                found => unmet.push(UnmetDependency {
+                    module: id.into(),
+                    dependency: dependency.id.clone(),
+                    versions: dependency.versions.clone(),
+                    found: found.map(|found| found.manifest.version.clone()),
+                }),
+            }
+        }
+
+        if unmet.is_empty() {
+            dependencies.insert(id, resolved);
+        } else {
+            failed.insert(id);
+            load_order
+                .failed
+                .push((info, ResolveError::UnmetDependencies(unmet)));
+        }
+    }
+
+    // Sort modules
+    let mut sorted = HashSet::default();
+    while !dependencies.is_empty() {
+        let next = dependencies
+            .iter()
+            .find(|(_, module_dependencies)| {
+                module_dependencies
+                    .iter()
+                    .all(|dependency| sorted.contains(dependency) || failed.contains(dependency))
+            })
+            .map(|(&id, module_dependencies)| {
+                let failed_dependency = module_dependencies
+                    .iter()
+                    .copied()
+                    .find(|dependency| failed.contains(dependency));
+                (id, failed_dependency)
+            });
+
+        match next {
+            Some((id, None)) => {
+                dependencies.remove(id);
+                sorted.insert(id);
+                load_order.modules.push(modules[id]);
+            }
+            Some((id, Some(dependency))) => {
+                dependencies.remove(id);
+                failed.insert(id);
+                load_order.failed.push((
+                    modules[id],
+                    ResolveError::DependencyFailed {
+                        module: id.into(),
+                        dependency: dependency.into(),
+                    },
+                ));
+            }
+            None => {
+                let cycle = find_cycle(&dependencies);
+                for &id in cycle.iter().skip(1) {
+                    dependencies.remove(id);
+                    failed.insert(id);
+                    let cycle_ids = cycle.iter().map(|&id| id.into()).collect();
+                    load_order
+                        .failed
+                        .push((modules[id], ResolveError::Cycle(cycle_ids)));
+                }
+            }
+        }
+    }
+
+    load_order
+}
+
+/// Finds a dependency cycle among the modules which haven't been sorted yet.
+/// Each of them has at least one unsorted dependency, otherwise it would have
+/// been sorted, so following them must eventually revisit a module. The
+/// returned cycle starts and ends with the same module.
+fn find_cycle<'a>(dependencies: &BTreeMap<&'a str, Vec<&'a str>>) -> Vec<&'a str> {
+    let mut path: Vec<&str> = Vec::new();
+    let mut current = *dependencies.keys().next().expect("no unsorted modules");
+    loop {
+        if let Some(start) = path.iter().position(|&id| id == current) {
+            path.push(current);
+            return path.split_off(start);
+        }
+
+        path.push(current);
+        current = dependencies[current]
+            .iter()
+            .copied()
+            .find(|dependency| dependencies.contains_key(dependency))
+            .expect("unsorted module has no unsorted dependencies");
+    }
+}
+
+#[derive(Clone, Debug, Display, Error)]
+pub enum ResolveError {
+    #[display(fmt = "unmet module dependencies: {}", "join(_0.as_slice(), \", \")")]
+    UnmetDependencies(#[error(ignore)] Vec<UnmetDependency>),
+
+    #[display(fmt = "dependency cycle between modules: {}", "_0.join(\" -> \")")]
+    Cycle(#[error(ignore)] Vec<String>),
+
+    #[display(fmt = "{} depends on {}, which failed to load", module, dependency)]
+    DependencyFailed { module: String, dependency: String },
+}
+
+/// A dependency of a module which is either missing or has an incompatible
+/// version.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnmetDependency {
+    pub module: String,
+    pub dependency: String,
+    pub versions: VersionReq,
+    pub found: Option<Version>,
+}
+
+impl Display for UnmetDependency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} requires {} {}, ",
+            self.module, self.dependency, self.versions
+        )?;
+        match &self.found {
+            Some(found) => write!(f, "but found {}", found),
+            None => write!(f, "but it is missing"),
+        }
+    }
+}
+
+fn join(items: &[impl Display], separator: &str) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::{ModuleDependency, ModuleManifest};
+    use std::path::PathBuf;
+
+    fn module(id: &str, version: &str, dependencies: &[(&str, &str, bool)]) -> ModuleInfo {
+        ModuleInfo {
+            manifest: ModuleManifest {
+                id: id.into(),
+                version: Version::parse(version).unwrap(),
+                entry: PathBuf::from("mod.wasm"),
+                dependencies: dependencies
+                    .iter()
+                    .map(|&(id, versions, optional)| ModuleDependency {
+                        id: id.into(),
+                        versions: VersionReq::parse(versions).unwrap(),
+                        optional,
+                    })
+                    .collect(),
+            },
+            entry_dir: PathBuf::new(),
+            entry_path: PathBuf::new(),
+            data_path: PathBuf::new(),
+        }
+    }
+
+    fn ids<'a>(modules: &[&'a ModuleInfo]) -> Vec<&'a str> {
+        modules
+            .iter()
+            .map(|info| info.manifest.id.as_str())
+            .collect()
+    }
+
+    fn failed<'a>(load_order: &'a LoadOrder) -> Vec<(&'a str, String)> {
+        load_order
+            .failed
+            .iter()
+            .map(|(info, error)| (info.manifest.id.as_str(), error.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_are_loaded_first() {
+        let modules = [
+            module("a", "1.0.0", &[("c", "^1", false)]),
+            module("b", "1.0.0", &[]),
+            module("c", "1.2.0", &[("b", ">=1.0.0", false)]),
+        ];
+        let load_order = resolve_load_order(&modules);
+        assert_eq!(vec!["b", "c", "a"], ids(&load_order.modules));
+        assert!(load_order.failed.is_empty());
+    }
+
+    #[test]
+    fn missing_optional_dependency_is_skipped() {
+        let modules = [module("a", "1.0.0", &[("b", "*", true)])];
+        let load_order = resolve_load_order(&modules);
+        assert_eq!(vec!["a"], ids(&load_order.modules));
+        assert!(load_order.failed.is_empty());
+    }
+
+    #[test]
+    fn present_optional_dependency_is_loaded_first() {
+        let modules = [
+            module("a", "1.0.0", &[("b", "*", true)]),
+            module("b", "1.0.0", &[]),
+        ];
+        let load_order = resolve_load_order(&modules);
+        assert_eq!(vec!["b", "a"], ids(&load_order.modules));
+    }
+
+    #[test]
+    fn unmet_dependencies_are_reported() {
+        let modules = [
+            module("a", "1.0.0", &[("b", "^2", false), ("c", "*", false)]),
+            module("b", "1.3.0", &[]),
+            module("d", "1.0.0", &[("b", "^2", true)]),
+        ];
+        let load_order = resolve_load_order(&modules);
+
+        assert_eq!(vec!["b"], ids(&load_order.modules));
+        assert_eq!(
+            vec![
+                (
+                    "a",
+                    "unmet module dependencies: a requires b ^2, but found 1.3.0, a requires c \
+                     *, but it is missing"
+                        .to_string()
+                ),
+                (
+                    "d",
+                    "unmet module dependencies: d requires b ^2, but found 1.3.0".to_string()
+                ),
+            ],
+            failed(&load_order)
+        );
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let modules = [
+            module("a", "1.0.0", &[]),
+            module("b", "1.0.0", &[("a", "*", false), ("d", "*", false)]),
+            module("c", "1.0.0", &[("b", "*", false)]),
+            module("d", "1.0.0", &[("c", "*", false)]),
+        ];
+        let load_order = resolve_load_order(&modules);
+
+        assert_eq!(vec!["a"], ids(&load_order.modules));
+        let message = "dependency cycle between modules: b -> d -> c -> b".to_string();
+        assert_eq!(
+            vec![
+                ("d", message.clone()),
+                ("c", message.clone()),
+                ("b", message)
+            ],
+            failed(&load_order)
+        );
+    }
+
+    #[test]
+    fn dependents_of_failed_modules_are_not_loaded() {
+        let modules = [
+            module("a", "1.0.0", &[("missing", "*", false)]),
+            module("b", "1.0.0", &[("a", "*", false)]),
+            module("c", "1.0.0", &[("b", "*", true)]),
+            module("d", "1.0.0", &[]),
+        ];
+        let load_order = resolve_load_order(&modules);
+
+        assert_eq!(vec!["d"], ids(&load_order.modules));
+        let failed: Vec<_> = failed(&load_order).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(vec!["a", "b", "c"], failed);
+        assert_eq!(
+            "b depends on a, which failed to load",
+            load_order.failed[1].1.to_string()
+        );
+    }
+}
@@ -1,13 +1,19 @@
-use crate::module::ModuleManifest;
+use crate::{
+    host::{game_namespace, tile_changed_params, HostEnv, HostState},
+    module::ModuleManifest,
+    runner::{resolve_load_order, ResolveError},
+};
 use game_lib::{
     anyhow::{anyhow, bail, Context},
-    bevy::utils::{HashMap, HashSet},
+    bevy::utils::HashMap,
 };
+use game_tiles::{GameWorld, Tile, TileChanged, TileWorldPosition};
 use std::{
     fmt::{Debug, Formatter},
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 use wasmer::{
     import_namespace, ExportError, Instance, Memory, MemoryType, Module, Pages, Store, Val,
@@ -36,149 +42,320 @@ impl Debug for ActiveModule {
     }
 }
 
+impl ActiveModule {
+    fn call_if_exists(
+        &self,
+        function_name: &str,
+        params: &[Val],
+    ) -> game_lib::anyhow::Result<Option<Box<[Val]>>> {
+        match self.instance.exports.get_function(function_name) {
+            Ok(function) => function
+                .call(params)
+                .context("error during function execution")
+                .map(Some),
+            Err(ExportError::Missing(_)) => Ok(None),
+            Err(error) => Err(error).context("error getting exported function"),
+        }
+    }
+}
+
+/// A module which was disabled because it failed to load or run.
+#[derive(Debug)]
+pub struct ModuleFailure {
+    /// The id of the module, or its directory if its manifest couldn't be read.
+    pub module: String,
+    pub error: game_lib::anyhow::Error,
+}
+
 pub struct WasmRunner {
     shared_memory: Memory,
-    active_modules: HashMap<String, ActiveModule>,
+    host: Arc<HostState>,
+    /// Loaded modules, in load order.
+    active_modules: Vec<ActiveModule>,
+    /// Failures which haven't been drained yet.
+    failures: Vec<ModuleFailure>,
 }
 
 impl WasmRunner {
+    /// Loads all the modules in `bin_path/mods`. Modules which fail to load
+    /// are skipped and recorded as failures, along with any modules depending
+    /// on them. This only fails if modules can't be loaded at all.
     pub fn new(bin_path: &Path) -> game_lib::anyhow::Result<Self> {
         let mod_path: PathBuf = [bin_path, Path::new("mods")].iter().collect();
         let mod_data_path: PathBuf = [bin_path, Path::new("mod_data")].iter().collect();
+        std::fs::create_dir_all(&mod_path)
+            .with_context(|| format!("failure creating mods directory {}", mod_path.display()))?;
 
         // Find modules
-        let mut modules = HashMap::default();
+        let mut failures = Vec::new();
+        let mut modules: HashMap<String, ModuleInfo> = HashMap::default();
         for dir in mod_path.read_dir()? {
-            let dir = dir?;
-            let dir_path = dir.path();
-
-            // Load manifest
-            let manifest_path: PathBuf = {
-                let mut path = dir_path.clone();
-                path.push("manifest.json");
-                path
-            };
-            let manifest_file = File::open(manifest_path)?;
-            let manifest: ModuleManifest =
-                game_lib::serde_json::from_reader(BufReader::new(manifest_file))?;
-
-            // Verify entry module is in the same directory
-            let entry_path: PathBuf = [&dir_path, &manifest.entry].iter().collect();
-            let entry_path = entry_path.canonicalize()?;
-            let data_path: PathBuf = match entry_path.strip_prefix(&dir_path) {
-                Ok(remainder) => [&mod_data_path, remainder].iter().collect(),
-                Err(_) => {
-                    bail!(
-                        "entry for {} must be contained in its directory ({})",
-                        manifest.id,
-                        dir_path.display()
-                    )
-                }
-            };
-
-            modules.insert(
-                manifest.id.clone(),
-                ModuleInfo {
-                    manifest,
-                    entry_dir: dir_path,
-                    entry_path,
-                    data_path,
+            let dir_path = dir?.path();
+            if !dir_path.is_dir() {
+                continue;
+            }
+
+            match Self::read_module_info(&dir_path, &mod_data_path) {
+                Ok(info) => match modules.get(&info.manifest.id) {
+                    Some(existing) => failures.push(ModuleFailure {
+                        module: info.manifest.id.clone(),
+                        error: anyhow!(
+                            "duplicate module id, already found in {}",
+                            existing.entry_dir.display()
+                        ),
+                    }),
+                    None => {
+                        modules.insert(info.manifest.id.clone(), info);
+                    }
                 },
-            );
+                Err(error) => failures.push(ModuleFailure {
+                    module: dir_path.display().to_string(),
+                    error,
+                }),
+            }
         }
 
-        let mut load_order = Vec::new();
-        let mut sorted = HashSet::default();
-        let mut unsorted: HashSet<_> = modules.keys().map(|s| s.as_str()).collect();
-        while !unsorted.is_empty() {
-            let next = unsorted
-                .iter()
-                .find_map(|&id| {
-                    modules.get(id).filter(|info| {
-                        info.manifest
-                            .dependencies
-                            .iter()
-                            .all(|dependency| sorted.contains(dependency.id.as_str()))
-                    })
-                })
-                .ok_or_else(|| anyhow!("could not resolve module load order"))?;
-
-            unsorted.remove(next.manifest.id.as_str());
-            sorted.insert(next.manifest.id.as_str());
-            load_order.push(next);
-        }
+        let load_order = resolve_load_order(modules.values());
+        failures.extend(
+            load_order
+                .failed
+                .into_iter()
+                .map(|(info, error)| ModuleFailure {
+                    module: info.manifest.id.clone(),
+                    error: error.into(),
+                }),
+        );
 
         // Load modules
         // TODO: make this multithreaded
         let store = Store::default();
         let shared_memory = Memory::new(&store, MemoryType::new(Pages(1024), None, true))
             .context("failure creating shared memory for modules")?;
-        let active_modules = HashMap::default();
-        for module_info in load_order {
-            let entry_bytes = std::fs::read(&module_info.entry_path)?;
-            let module = Module::new(&store, &entry_bytes)?;
-            let mut wasi_env = WasiState::new("mod_core")
-                .preopen(|p| {
-                    p.directory(&module_info.entry_dir)
-                        .read(true)
-                        .write(false)
-                        .create(false)
-                        .alias("/mod")
-                })?
-                .preopen(|p| {
-                    p.directory(&module_info.data_path)
-                        .read(true)
-                        .write(true)
-                        .create(true)
-                        .alias("/data")
-                })?
-                .env("PWD", "/")
-                .finalize()?;
-            let mut imports = wasi_env.import_object(&module)?;
-            let env_namespace = import_namespace! {
-                {
-                    "memory" => shared_memory.clone(),
-                }
-            };
-            imports.register("env", env_namespace);
-            let instance = Instance::new(&module, &imports)?;
-
-            // Call _start function by convention
-            if let Ok(function) = instance.exports.get_native_function::<(), ()>("_start") {
-                function.call()?;
+        let host = Arc::new(HostState::default());
+        let mut active_modules: Vec<ActiveModule> = Vec::with_capacity(load_order.modules.len());
+        for module_info in load_order.modules {
+            // Dependencies are loaded first, so any present dependency which
+            // isn't active failed to load
+            let failed_dependency = module_info
+                .manifest
+                .dependencies
+                .iter()
+                .filter(|dependency| modules.contains_key(&dependency.id))
+                .find(|dependency| {
+                    !active_modules
+                        .iter()
+                        .any(|active_module| active_module.info.manifest.id == dependency.id)
+                });
+            if let Some(dependency) = failed_dependency {
+                let error = ResolveError::DependencyFailed {
+                    module: module_info.manifest.id.clone(),
+                    dependency: dependency.id.clone(),
+                };
+                failures.push(ModuleFailure {
+                    module: module_info.manifest.id.clone(),
+                    error: error.into(),
+                });
+                continue;
+            }
+
+            match Self::instantiate(&store, &shared_memory, &host, module_info) {
+                Ok(instance) => active_modules.push(ActiveModule {
+                    info: module_info.clone(),
+                    instance,
+                }),
+                Err(error) => failures.push(ModuleFailure {
+                    module: module_info.manifest.id.clone(),
+                    error,
+                }),
             }
         }
 
         Ok(WasmRunner {
             shared_memory,
+            host,
             active_modules,
+            failures,
         })
     }
 
-    pub fn on_update(&mut self, params: &[Val]) -> game_lib::anyhow::Result<()> {
-        self.call_all_if_exists("on_update", params)
-            .into_iter()
-            .map(|(_, result)| result.map(|_| ()))
-            .collect()
+    fn read_module_info(
+        dir_path: &Path,
+        mod_data_path: &Path,
+    ) -> game_lib::anyhow::Result<ModuleInfo> {
+        let dir_path = dir_path.canonicalize()?;
+
+        // Load manifest
+        let manifest_path: PathBuf = {
+            let mut path = dir_path.clone();
+            path.push("manifest.json");
+            path
+        };
+        let manifest_file = File::open(&manifest_path)
+            .with_context(|| format!("failure opening {}", manifest_path.display()))?;
+        let manifest: ModuleManifest =
+            game_lib::serde_json::from_reader(BufReader::new(manifest_file))
+                .with_context(|| format!("failure reading {}", manifest_path.display()))?;
+
+        // Verify entry module is in the same directory
+        let entry_path: PathBuf = [&dir_path, &manifest.entry].iter().collect();
+        let entry_path = entry_path
+            .canonicalize()
+            .with_context(|| format!("failure finding entry {}", entry_path.display()))?;
+        if !entry_path.starts_with(&dir_path) {
+            bail!(
+                "entry for {} must be contained in its directory ({})",
+                manifest.id,
+                dir_path.display()
+            );
+        }
+
+        let data_path: PathBuf = [mod_data_path, Path::new(&manifest.id)].iter().collect();
+        Ok(ModuleInfo {
+            manifest,
+            entry_dir: dir_path,
+            entry_path,
+            data_path,
+        })
+    }
+
+    fn instantiate(
+        store: &Store,
+        shared_memory: &Memory,
+        host: &Arc<HostState>,
+        module_info: &ModuleInfo,
+    ) -> game_lib::anyhow::Result<Instance> {
+        std::fs::create_dir_all(&module_info.data_path)?;
+        let entry_bytes = std::fs::read(&module_info.entry_path)?;
+        let module = Module::new(store, &entry_bytes)?;
+        let mut wasi_env = WasiState::new("mod_core")
+            .preopen(|p| {
+                p.directory(&module_info.entry_dir)
+                    .read(true)
+                    .write(false)
+                    .create(false)
+                    .alias("/mod")
+            })?
+            .preopen(|p| {
+                p.directory(&module_info.data_path)
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .alias("/data")
+            })?
+            .env("PWD", "/")
+            .finalize()?;
+        let mut imports = wasi_env.import_object(&module)?;
+        let env_namespace = import_namespace! {
+            {
+                "memory" => shared_memory.clone(),
+            }
+        };
+        imports.register("env", env_namespace);
+        let host_env = HostEnv::new(module_info.manifest.id.clone(), host.clone());
+        imports.register("game", game_namespace(store, host_env));
+        let instance = Instance::new(&module, &imports)?;
+
+        // Call _start function by convention
+        if let Ok(function) = instance.exports.get_native_function::<(), ()>("_start") {
+            function.call().context("error during _start")?;
+        }
+
+        Ok(instance)
+    }
+
+    /// Iterates over the loaded modules in load order.
+    pub fn modules(&self) -> impl Iterator<Item = (&ModuleInfo, &Instance)> {
+        self.active_modules
+            .iter()
+            .map(|active_module| (&active_module.info, &active_module.instance))
+    }
+
+    /// Gets the instance of a loaded module by its id.
+    pub fn get(&self, id: &str) -> Option<&Instance> {
+        self.active_modules
+            .iter()
+            .find(|active_module| active_module.info.manifest.id == id)
+            .map(|active_module| &active_module.instance)
+    }
+
+    /// Takes the failures recorded since the last time this was called.
+    /// Each failed module has already been disabled.
+    pub fn drain_failures(&mut self) -> impl Iterator<Item = ModuleFailure> + '_ {
+        self.failures.drain(..)
+    }
+
+    /// Lets modules read `world` while `f` runs. Edits made by modules are
+    /// returned afterwards instead of being applied, so the world only needs
+    /// to be borrowed mutably if a module changed it.
+    pub fn with_world<R>(
+        &mut self,
+        world: &GameWorld,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> (R, Vec<(TileWorldPosition, Option<Tile>)>) {
+        let host = self.host.clone();
+        let result = host.lend_world(world, || f(self));
+        (result, host.take_edits())
+    }
+
+    pub fn on_update(&mut self, params: &[Val]) {
+        self.call_hook("on_update", params);
+    }
+
+    /// Calls `on_tile_changed` on each module which subscribed to tile
+    /// changes.
+    pub fn on_tile_changed(&mut self, change: &TileChanged) {
+        let host = self.host.clone();
+        self.call_hook_where("on_tile_changed", &tile_changed_params(change), |info| {
+            host.is_subscribed_to_tiles(&info.manifest.id)
+        });
+    }
+
+    /// Calls a function on each module which exports it, in load order. Any
+    /// module which fails is disabled and its failure is recorded.
+    pub fn call_hook(&mut self, function_name: &str, params: &[Val]) {
+        self.call_hook_where(function_name, params, |_| true);
+    }
+
+    fn call_hook_where(
+        &mut self,
+        function_name: &str,
+        params: &[Val],
+        filter: impl Fn(&ModuleInfo) -> bool,
+    ) {
+        let failed: Vec<_> = self
+            .active_modules
+            .iter()
+            .filter(|active_module| filter(&active_module.info))
+            .filter_map(|active_module| {
+                active_module
+                    .call_if_exists(function_name, params)
+                    .err()
+                    .map(|error| (active_module.info.manifest.id.clone(), error))
+            })
+            .collect();
+
+        for (id, error) in failed {
+            self.active_modules
+                .retain(|active_module| active_module.info.manifest.id != id);
+            self.failures.push(ModuleFailure {
+                module: id,
+                error: error.context(format!("error calling {}", function_name)),
+            });
+        }
     }
 
     pub fn call_all_if_exists(
         &mut self,
         function_name: &str,
         params: &[Val],
-    ) -> HashMap<&ModuleInfo, game_lib::anyhow::Result<Option<Box<[Val]>>>> {
+    ) -> Vec<(&ModuleInfo, game_lib::anyhow::Result<Option<Box<[Val]>>>)> {
         self.active_modules
             .iter()
-            .map(|(_id, active_module)| {
-                let result = match active_module.instance.exports.get_function(function_name) {
-                    Ok(function) => function
-                        .call(params)
-                        .context("error during function execution")
-                        .map(|result| Some(result)),
-                    Err(ExportError::Missing(_)) => Ok(None),
-                    Err(error) => Err(error).context("error getting exported function"),
-                };
-                (&active_module.info, result)
+            .map(|active_module| {
+                (
+                    &active_module.info,
+                    active_module.call_if_exists(function_name, params),
+                )
             })
             .collect()
     }
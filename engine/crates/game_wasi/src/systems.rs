@@ -1,18 +1,65 @@
-use crate::runner::WasmRunner;
+use crate::{runner::WasmRunner, ModError};
 use game_lib::{
     bevy::prelude::*,
     tracing::{self, instrument},
 };
+use game_tiles::{GameWorld, TileChanged};
 use std::path::Path;
 
-#[instrument(skip(commands))]
-pub fn setup_runner(mut commands: Commands) {
+#[instrument(skip(commands, mod_errors))]
+pub fn setup_runner(mut commands: Commands, mut mod_errors: EventWriter<ModError>) {
     // TODO: actually load module properly from a directory
-    let runner = WasmRunner::new(Path::new("bin/mods")).unwrap();
-    commands.insert_resource(runner);
+    match WasmRunner::new(Path::new("bin")) {
+        Ok(mut runner) => {
+            report_failures(&mut runner, &mut mod_errors);
+            commands.insert_resource(runner);
+        }
+        Err(error) => error!("failed to load modules: {:#}", error),
+    }
 }
 
-#[instrument(skip(runner))]
-pub fn on_update(mut runner: ResMut<WasmRunner>) {
-    runner.on_update(&[]).unwrap();
+#[instrument(skip(runner, world, tile_changed, mod_errors))]
+pub fn on_update(
+    runner: Option<ResMut<WasmRunner>>,
+    world: Option<ResMut<GameWorld>>,
+    mut tile_changed: EventReader<TileChanged>,
+    mut mod_errors: EventWriter<ModError>,
+) {
+    let mut runner = match runner {
+        Some(runner) => runner,
+        None => return,
+    };
+
+    match world {
+        Some(mut world) => {
+            // Only borrow the world mutably if a module edited it so it isn't
+            // flagged as changed every frame
+            let (_, edits) = runner.with_world(&world, |runner| {
+                for change in tile_changed.iter() {
+                    runner.on_tile_changed(change);
+                }
+
+                runner.on_update(&[]);
+            });
+
+            for (position, tile) in edits {
+                if let Err(error) = world.set_tile(position, tile) {
+                    warn!("module failed to set tile at {}: {}", position, error);
+                }
+            }
+        }
+        None => runner.on_update(&[]),
+    }
+
+    report_failures(&mut runner, &mut mod_errors);
+}
+
+fn report_failures(runner: &mut WasmRunner, mod_errors: &mut EventWriter<ModError>) {
+    for failure in runner.drain_failures() {
+        error!("disabled module {}: {:#}", failure.module, failure.error);
+        mod_errors.send(ModError {
+            module: failure.module,
+            message: format!("{:#}", failure.error),
+        });
+    }
 }
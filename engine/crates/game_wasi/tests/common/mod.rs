@@ -0,0 +1,94 @@
+//! Helpers for loading fixture modules in integration tests.
+
+// Not every test uses every part of the harness
+#![allow(dead_code)]
+
+use game_wasi::runner::WasmRunner;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Path to the prebuilt counter module. See `fixtures/counter/counter.wat`.
+pub const COUNTER_WASM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/counter/counter.wasm"
+);
+
+/// Path to the prebuilt tiles module. See `fixtures/tiles/tiles.wat`.
+pub const TILES_WASM: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/tiles/tiles.wasm"
+);
+
+/// Path to the prebuilt trap module. See `fixtures/trap/trap.wat`.
+pub const TRAP_WASM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/trap/trap.wasm");
+
+/// A temporary `bin` directory containing a `mods` directory. The directory
+/// is removed when this is dropped.
+pub struct ModsDirectory {
+    bin_path: PathBuf,
+}
+
+impl ModsDirectory {
+    pub fn new() -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let bin_path = std::env::temp_dir().join(format!(
+            "game_wasi_test_{}_{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(bin_path.join("mods")).unwrap();
+        ModsDirectory { bin_path }
+    }
+
+    pub fn bin_path(&self) -> &Path {
+        &self.bin_path
+    }
+
+    /// Adds a module directory containing a copy of `wasm_path` and the given
+    /// manifest. The copy keeps the file name of `wasm_path`.
+    pub fn add_module(&self, dir_name: &str, wasm_path: &str, manifest: &str) -> &Self {
+        let mod_path = self.add_dir(dir_name);
+        let file_name = Path::new(wasm_path).file_name().unwrap();
+        std::fs::copy(wasm_path, mod_path.join(file_name)).unwrap();
+        std::fs::write(mod_path.join("manifest.json"), manifest).unwrap();
+        self
+    }
+
+    /// Adds a copy of the counter module with the given manifest. The entry
+    /// is always `counter.wasm`.
+    pub fn add_counter(&self, dir_name: &str, manifest: &str) -> &Self {
+        self.add_module(dir_name, COUNTER_WASM, manifest)
+    }
+
+    /// Adds an empty module directory.
+    pub fn add_dir(&self, dir_name: &str) -> PathBuf {
+        let mod_path = self.bin_path.join("mods").join(dir_name);
+        std::fs::create_dir_all(&mod_path).unwrap();
+        mod_path
+    }
+
+    pub fn load(&self) -> WasmRunner {
+        WasmRunner::new(&self.bin_path).unwrap()
+    }
+}
+
+impl Drop for ModsDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.bin_path);
+    }
+}
+
+/// Reads an exported `i32` global from a loaded module.
+pub fn read_global(runner: &WasmRunner, id: &str, name: &str) -> i32 {
+    runner
+        .get(id)
+        .unwrap_or_else(|| panic!("module {:?} was not loaded", id))
+        .exports
+        .get_global(name)
+        .unwrap()
+        .get()
+        .unwrap_i32()
+}
@@ -0,0 +1,9 @@
+;; Source for counter.wasm. Counts how many times each hook has been called.
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+  (global $starts (export "starts") (mut i32) (i32.const 0))
+  (global $updates (export "updates") (mut i32) (i32.const 0))
+  (func (export "_start")
+    (global.set $starts (i32.add (global.get $starts) (i32.const 1))))
+  (func (export "on_update")
+    (global.set $updates (i32.add (global.get $updates) (i32.const 1)))))
@@ -0,0 +1,31 @@
+;; Source for tiles.wasm. Exercises the `game` host functions and records the
+;; results in globals.
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+  (import "game" "get_tile" (func $get_tile (param i32 i32) (result i32)))
+  (import "game" "set_tile" (func $set_tile (param i32 i32 i32) (result i32)))
+  (import "game" "region_state" (func $region_state (param i32 i32) (result i32)))
+  (import "game" "subscribe_tile_changes" (func $subscribe_tile_changes))
+  (global $tile (export "tile") (mut i32) (i32.const 0))
+  (global $region (export "region") (mut i32) (i32.const 0))
+  (global $old (export "old") (mut i32) (i32.const 0))
+  (global $after_set (export "after_set") (mut i32) (i32.const 0))
+  (global $far_region (export "far_region") (mut i32) (i32.const 0))
+  (global $far_tile (export "far_tile") (mut i32) (i32.const 0))
+  (global $changes (export "changes") (mut i32) (i32.const 0))
+  (global $change_x (export "change_x") (mut i32) (i32.const 0))
+  (global $change_new (export "change_new") (mut i32) (i32.const 0))
+  (func (export "_start")
+    (call $subscribe_tile_changes))
+  (func (export "on_update")
+    (global.set $tile (call $get_tile (i32.const 0) (i32.const 0)))
+    (global.set $region (call $region_state (i32.const 0) (i32.const 0)))
+    ;; Place dirt at (1, 0)
+    (global.set $old (call $set_tile (i32.const 1) (i32.const 0) (i32.const 1)))
+    (global.set $after_set (call $get_tile (i32.const 1) (i32.const 0)))
+    (global.set $far_region (call $region_state (i32.const 100) (i32.const 0)))
+    (global.set $far_tile (call $get_tile (i32.const 1600) (i32.const 0))))
+  (func (export "on_tile_changed") (param $x i32) (param $y i32) (param $old i32) (param $new i32)
+    (global.set $changes (i32.add (global.get $changes) (i32.const 1)))
+    (global.set $change_x (local.get $x))
+    (global.set $change_new (local.get $new))))
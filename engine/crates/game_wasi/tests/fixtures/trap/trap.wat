@@ -0,0 +1,5 @@
+;; Source for trap.wasm. Traps whenever on_update is called.
+(module
+  (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+  (func (export "on_update")
+    unreachable))
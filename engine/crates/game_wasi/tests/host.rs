@@ -0,0 +1,130 @@
+mod common;
+
+use common::{read_global, ModsDirectory, TILES_WASM};
+use game_tiles::{
+    generation::FlatWorldGenerator, GameWorld, RegionWorldPosition, Tile, TileWorldPosition,
+};
+use game_wasi::{
+    host::{
+        tile_to_id, ERROR_NOT_GENERATED, ERROR_NO_WORLD, REGION_GENERATED, REGION_NOT_GENERATED,
+        TILE_NONE,
+    },
+    runner::WasmRunner,
+};
+use std::panic::{self, AssertUnwindSafe};
+
+/// An empty world with only the region at the origin generated, and ice at
+/// `(0, 0)`.
+fn world() -> GameWorld {
+    let mut world = GameWorld::new(Box::new(FlatWorldGenerator::new(Tile::Stone, Some(0))));
+    world.get_or_generate_region(RegionWorldPosition::ZERO);
+    *world.get_or_generate_tile(TileWorldPosition::new(0, 0)) = Some(Tile::Ice);
+    world
+}
+
+fn load_tiles(mods: &ModsDirectory) -> WasmRunner {
+    mods.add_module(
+        "tiles",
+        TILES_WASM,
+        r#"{ "id": "tiles", "version": "0.1.0", "entry": "tiles.wasm" }"#,
+    );
+    let mut runner = mods.load();
+    assert_eq!(0, runner.drain_failures().count());
+    runner
+}
+
+#[test]
+fn world_is_unavailable_unless_lent() {
+    let mods = ModsDirectory::new();
+    let mut runner = load_tiles(&mods);
+    runner.on_update(&[]);
+
+    assert_eq!(0, runner.drain_failures().count());
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "tile"));
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "region"));
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "old"));
+}
+
+#[test]
+fn world_is_unavailable_after_loan_ends() {
+    let mods = ModsDirectory::new();
+    let mut runner = load_tiles(&mods);
+    let world = world();
+    runner.with_world(&world, |runner| runner.on_update(&[]));
+    assert_eq!(
+        tile_to_id(Some(Tile::Ice)),
+        read_global(&runner, "tiles", "tile")
+    );
+
+    // The world is gone, so the host must not still be pointing at it
+    drop(world);
+    runner.on_update(&[]);
+
+    assert_eq!(0, runner.drain_failures().count());
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "tile"));
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "region"));
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "old"));
+}
+
+#[test]
+fn world_is_unavailable_after_loan_panics() {
+    let mods = ModsDirectory::new();
+    let mut runner = load_tiles(&mods);
+    let world = world();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        runner.with_world(&world, |_| panic!("hook panicked"));
+    }));
+    assert!(result.is_err());
+
+    drop(world);
+    runner.on_update(&[]);
+    assert_eq!(ERROR_NO_WORLD, read_global(&runner, "tiles", "tile"));
+}
+
+#[test]
+fn modules_read_and_edit_lent_world() {
+    let mods = ModsDirectory::new();
+    let mut runner = load_tiles(&mods);
+    let world = world();
+    let (_, edits) = runner.with_world(&world, |runner| runner.on_update(&[]));
+
+    let read = |name| read_global(&runner, "tiles", name);
+    assert_eq!(tile_to_id(Some(Tile::Ice)), read("tile"));
+    assert_eq!(REGION_GENERATED, read("region"));
+    assert_eq!(TILE_NONE, read("old"));
+    assert_eq!(tile_to_id(Some(Tile::Dirt)), read("after_set"));
+    assert_eq!(REGION_NOT_GENERATED, read("far_region"));
+    assert_eq!(ERROR_NOT_GENERATED, read("far_tile"));
+
+    // Edits are returned instead of being applied
+    assert_eq!(
+        vec![(TileWorldPosition::new(1, 0), Some(Tile::Dirt))],
+        edits
+    );
+    assert_eq!(None, *world.get_tile(TileWorldPosition::new(1, 0)).unwrap());
+}
+
+#[test]
+fn subscribers_receive_tile_changes() {
+    let mods = ModsDirectory::new();
+    let mut runner = load_tiles(&mods);
+    let mut world = world();
+    let (_, edits) = runner.with_world(&world, |runner| runner.on_update(&[]));
+    for (position, tile) in edits {
+        world.set_tile(position, tile).unwrap();
+    }
+
+    let changes: Vec<_> = world.take_changes().collect();
+    assert_eq!(1, changes.len());
+    for change in changes.iter() {
+        runner.on_tile_changed(change);
+    }
+
+    assert_eq!(0, runner.drain_failures().count());
+    assert_eq!(1, read_global(&runner, "tiles", "changes"));
+    assert_eq!(1, read_global(&runner, "tiles", "change_x"));
+    assert_eq!(
+        tile_to_id(Some(Tile::Dirt)),
+        read_global(&runner, "tiles", "change_new")
+    );
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use common::{read_global, ModsDirectory, TRAP_WASM};
+use game_wasi::runner::WasmRunner;
+
+#[test]
+fn no_modules() {
+    let mods = ModsDirectory::new();
+    let mut runner = mods.load();
+
+    assert_eq!(0, runner.modules().count());
+    runner.on_update(&[]);
+}
+
+#[test]
+fn module_is_started_once() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "counter",
+        r#"{ "id": "counter", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+    runner.on_update(&[]);
+
+    assert_eq!(1, read_global(&runner, "counter", "starts"));
+}
+
+#[test]
+fn on_update_is_called() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "counter",
+        r#"{ "id": "counter", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+
+    assert_eq!(0, read_global(&runner, "counter", "updates"));
+    runner.on_update(&[]);
+    runner.on_update(&[]);
+    runner.on_update(&[]);
+    assert_eq!(3, read_global(&runner, "counter", "updates"));
+}
+
+#[test]
+fn modules_are_called_in_load_order() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "a",
+        r#"{
+            "id": "a",
+            "version": "0.1.0",
+            "entry": "counter.wasm",
+            "dependencies": [{ "id": "c", "versions": "*" }]
+        }"#,
+    )
+    .add_counter(
+        "b",
+        r#"{
+            "id": "b",
+            "version": "0.1.0",
+            "entry": "counter.wasm",
+            "dependencies": [{ "id": "a", "versions": "*" }]
+        }"#,
+    )
+    .add_counter(
+        "c",
+        r#"{ "id": "c", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+
+    let order: Vec<_> = runner
+        .modules()
+        .map(|(info, _)| info.manifest.id.as_str())
+        .collect();
+    assert_eq!(vec!["c", "a", "b"], order);
+
+    let called: Vec<_> = runner
+        .call_all_if_exists("on_update", &[])
+        .into_iter()
+        .map(|(info, result)| {
+            assert!(result.unwrap().is_some());
+            info.manifest.id.clone()
+        })
+        .collect();
+    assert_eq!(vec!["c", "a", "b"], called);
+}
+
+/// Drains the runner's failures as `(module, message)` pairs.
+fn failures(runner: &mut WasmRunner) -> Vec<(String, String)> {
+    runner
+        .drain_failures()
+        .map(|failure| (failure.module, format!("{:#}", failure.error)))
+        .collect()
+}
+
+fn loaded(runner: &WasmRunner) -> Vec<&str> {
+    runner
+        .modules()
+        .map(|(info, _)| info.manifest.id.as_str())
+        .collect()
+}
+
+#[test]
+fn incompatible_dependency_is_disabled() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "a",
+        r#"{
+            "id": "a",
+            "version": "0.1.0",
+            "entry": "counter.wasm",
+            "dependencies": [{ "id": "b", "versions": "^0.2" }]
+        }"#,
+    )
+    .add_counter(
+        "b",
+        r#"{ "id": "b", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+
+    assert_eq!(vec!["b"], loaded(&runner));
+    assert_eq!(
+        vec![(
+            "a".to_string(),
+            "unmet module dependencies: a requires b ^0.2, but found 0.1.0".to_string()
+        )],
+        failures(&mut runner)
+    );
+}
+
+#[test]
+fn broken_module_directories_are_skipped() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "bad_entry",
+        r#"{ "id": "bad_entry", "version": "0.1.0", "entry": "missing.wasm" }"#,
+    )
+    .add_counter(
+        "counter",
+        r#"{ "id": "counter", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    )
+    .add_dir("no_manifest");
+    let mut runner = mods.load();
+    runner.on_update(&[]);
+
+    assert_eq!(vec!["counter"], loaded(&runner));
+    assert_eq!(1, read_global(&runner, "counter", "updates"));
+
+    let mut failed: Vec<_> = failures(&mut runner)
+        .into_iter()
+        .map(|(module, _)| module)
+        .collect();
+    failed.sort();
+    assert_eq!(2, failed.len());
+    assert!(failed[0].ends_with("bad_entry"));
+    assert!(failed[1].ends_with("no_manifest"));
+}
+
+#[test]
+fn dependents_of_failed_modules_are_disabled() {
+    let mods = ModsDirectory::new();
+    mods.add_counter(
+        "broken",
+        // The entry exists but isn't a valid module
+        r#"{ "id": "broken", "version": "0.1.0", "entry": "manifest.json" }"#,
+    )
+    .add_counter(
+        "dependent",
+        r#"{
+            "id": "dependent",
+            "version": "0.1.0",
+            "entry": "counter.wasm",
+            "dependencies": [{ "id": "broken", "versions": "*" }]
+        }"#,
+    )
+    .add_counter(
+        "independent",
+        r#"{ "id": "independent", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+
+    assert_eq!(vec!["independent"], loaded(&runner));
+    let failures = failures(&mut runner);
+    assert_eq!(2, failures.len());
+    assert_eq!("broken", failures[0].0);
+    assert_eq!(
+        (
+            "dependent".to_string(),
+            "dependent depends on broken, which failed to load".to_string()
+        ),
+        failures[1]
+    );
+}
+
+#[test]
+fn trapping_module_is_disabled() {
+    let mods = ModsDirectory::new();
+    mods.add_module(
+        "trap",
+        TRAP_WASM,
+        r#"{ "id": "trap", "version": "0.1.0", "entry": "trap.wasm" }"#,
+    )
+    .add_counter(
+        "counter",
+        r#"{ "id": "counter", "version": "0.1.0", "entry": "counter.wasm" }"#,
+    );
+    let mut runner = mods.load();
+    assert!(failures(&mut runner).is_empty());
+
+    runner.on_update(&[]);
+    let failures = failures(&mut runner);
+    assert_eq!(1, failures.len());
+    assert_eq!("trap", failures[0].0);
+    assert!(failures[0].1.starts_with("error calling on_update"));
+    assert_eq!(vec!["counter"], loaded(&runner));
+
+    runner.on_update(&[]);
+    assert_eq!(2, read_global(&runner, "counter", "updates"));
+}
@@ -1 +1,2 @@
 pub mod std;
+pub mod world;
@@ -0,0 +1,185 @@
+//! Safe wrappers around the `game` functions exposed by the host for reading
+//! and editing the world.
+
+/// Raw host functions. Tiles are passed as ids, with negative values for
+/// empty tiles and errors.
+mod ffi {
+    #[link(wasm_import_module = "game")]
+    extern "C" {
+        pub fn get_tile(x: i32, y: i32) -> i32;
+        pub fn set_tile(x: i32, y: i32, tile: i32) -> i32;
+        pub fn region_state(x: i32, y: i32) -> i32;
+        pub fn subscribe_tile_changes();
+        pub fn unsubscribe_tile_changes();
+    }
+
+    pub const TILE_NONE: i32 = -1;
+    pub const ERROR_NOT_GENERATED: i32 = -2;
+    pub const ERROR_NO_WORLD: i32 = -3;
+    pub const ERROR_INVALID_TILE: i32 = -4;
+
+    pub const REGION_NOT_GENERATED: i32 = 0;
+    pub const REGION_GENERATED: i32 = 1;
+}
+
+/// A tile in the world. The discriminants are the tile ids used by the host,
+/// and must be kept in sync with `tile_to_id` and `tile_from_id` in
+/// `engine/crates/game_wasi/src/host.rs`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[repr(i32)]
+pub enum Tile {
+    Stone = 0,
+    Dirt = 1,
+    StoneSlab = 2,
+    WoodPlatform = 3,
+    StoneSlopeLeft = 4,
+    StoneSlopeRight = 5,
+    StoneShallowSlopeLeftLower = 6,
+    StoneShallowSlopeLeftUpper = 7,
+    StoneShallowSlopeRightLower = 8,
+    StoneShallowSlopeRightUpper = 9,
+    Ice = 10,
+    Slime = 11,
+    Water = 12,
+}
+
+impl Tile {
+    /// Every tile, indexed by its id. New tiles must be added here as well as
+    /// to the host's `tile_to_id` and `tile_from_id`.
+    const ALL: [Tile; 13] = [
+        Tile::Stone,
+        Tile::Dirt,
+        Tile::StoneSlab,
+        Tile::WoodPlatform,
+        Tile::StoneSlopeLeft,
+        Tile::StoneSlopeRight,
+        Tile::StoneShallowSlopeLeftLower,
+        Tile::StoneShallowSlopeLeftUpper,
+        Tile::StoneShallowSlopeRightLower,
+        Tile::StoneShallowSlopeRightUpper,
+        Tile::Ice,
+        Tile::Slime,
+        Tile::Water,
+    ];
+
+    /// Converts a tile id from the host into a tile.
+    pub fn from_id(id: i32) -> Result<Option<Tile>, WorldError> {
+        match id {
+            ffi::TILE_NONE => Ok(None),
+            id if id >= 0 => Tile::ALL
+                .get(id as usize)
+                .copied()
+                .map(Some)
+                .ok_or(WorldError::InvalidTile),
+            error => Err(WorldError::from_code(error)),
+        }
+    }
+
+    pub fn to_id(tile: Option<Tile>) -> i32 {
+        tile.map_or(ffi::TILE_NONE, |tile| tile as i32)
+    }
+}
+
+/// A position of a tile within the world.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct TileWorldPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TileWorldPosition {
+    pub const fn new(x: i32, y: i32) -> Self {
+        TileWorldPosition { x, y }
+    }
+}
+
+/// A position of a region within the world. Each region is 16 by 16 tiles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct RegionWorldPosition {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl RegionWorldPosition {
+    pub const fn new(x: i32, y: i32) -> Self {
+        RegionWorldPosition { x, y }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum WorldError {
+    /// The region containing the tile hasn't been generated yet.
+    NotGenerated,
+    /// There is no world right now, for example because the game is in a
+    /// menu.
+    NoWorld,
+    /// The host didn't recognize a tile.
+    InvalidTile,
+    /// The host returned an error this version of the API doesn't know about.
+    Unknown(i32),
+}
+
+impl WorldError {
+    fn from_code(code: i32) -> Self {
+        match code {
+            ffi::ERROR_NOT_GENERATED => WorldError::NotGenerated,
+            ffi::ERROR_NO_WORLD => WorldError::NoWorld,
+            ffi::ERROR_INVALID_TILE => WorldError::InvalidTile,
+            code => WorldError::Unknown(code),
+        }
+    }
+}
+
+/// Gets the tile at a position. This includes edits made through
+/// [`set_tile`] earlier in the same update.
+pub fn get_tile(position: TileWorldPosition) -> Result<Option<Tile>, WorldError> {
+    Tile::from_id(unsafe { ffi::get_tile(position.x, position.y) })
+}
+
+/// Replaces the tile at a position, returning the old tile. The world is
+/// updated once the current hook returns.
+pub fn set_tile(
+    position: TileWorldPosition,
+    tile: Option<Tile>,
+) -> Result<Option<Tile>, WorldError> {
+    Tile::from_id(unsafe { ffi::set_tile(position.x, position.y, Tile::to_id(tile)) })
+}
+
+/// Checks whether a region has been generated yet.
+pub fn is_region_generated(position: RegionWorldPosition) -> Result<bool, WorldError> {
+    match unsafe { ffi::region_state(position.x, position.y) } {
+        ffi::REGION_NOT_GENERATED => Ok(false),
+        ffi::REGION_GENERATED => Ok(true),
+        error => Err(WorldError::from_code(error)),
+    }
+}
+
+/// A tile in the world was replaced.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct TileChanged {
+    pub position: TileWorldPosition,
+    pub old: Option<Tile>,
+    pub new: Option<Tile>,
+}
+
+impl TileChanged {
+    /// Converts the parameters passed to `on_tile_changed` by the host.
+    pub fn from_raw(x: i32, y: i32, old: i32, new: i32) -> Result<Self, WorldError> {
+        Ok(TileChanged {
+            position: TileWorldPosition::new(x, y),
+            old: Tile::from_id(old)?,
+            new: Tile::from_id(new)?,
+        })
+    }
+}
+
+/// Starts calling the module's `on_tile_changed(x, y, old, new)` export
+/// whenever a tile in the world changes. Use [`TileChanged::from_raw`] to
+/// read the parameters.
+pub fn subscribe_tile_changes() {
+    unsafe { ffi::subscribe_tile_changes() }
+}
+
+pub fn unsubscribe_tile_changes() {
+    unsafe { ffi::unsubscribe_tile_changes() }
+}
@@ -1,9 +1,29 @@
+use mod_api::world::{self, TileChanged};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Number of tiles changed since the last update.
+static TILE_CHANGES: AtomicU32 = AtomicU32::new(0);
+
 #[no_mangle]
 extern "C" fn _start() {
     println!("Loaded module :D");
+    world::subscribe_tile_changes();
 }
 
 #[no_mangle]
 extern "C" fn on_update() {
-    println!("Hello from wasi :D");
+    let changes = TILE_CHANGES.swap(0, Ordering::Relaxed);
+    if changes > 0 {
+        println!("{} tiles changed", changes);
+    }
+}
+
+#[no_mangle]
+extern "C" fn on_tile_changed(x: i32, y: i32, old: i32, new: i32) {
+    match TileChanged::from_raw(x, y, old, new) {
+        Ok(_) => {
+            TILE_CHANGES.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(error) => println!("Received an invalid tile change: {:?}", error),
+    }
 }
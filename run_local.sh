@@ -16,4 +16,4 @@ mkdir -p ./bin/mods/
 
 mkdir -p ./bin/mods/mod_core
 cp ./modules/target/cargo/wasm32-wasi/debug/mod_core.wasm ./bin/mods/mod_core/
-echo "{ \"id\": \"core\", \"version\": \"0.1\", \"entry\": \"mod_core.wasm\" }" > ./bin/mods/mod_core/manifest.json
+echo "{ \"id\": \"core\", \"version\": \"0.1.0\", \"entry\": \"mod_core.wasm\" }" > ./bin/mods/mod_core/manifest.json